@@ -0,0 +1,116 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mocopi_parser::{
+    encode_frame, encode_skeleton, parse, parse_trans, parse_value, Bone, BoneTrans, Frame,
+    FramePacket, Head, Info, Position, Rotation, Skeleton, SkeletonPacket, Transform,
+};
+use std::net::Ipv4Addr;
+
+fn head() -> Head {
+    Head {
+        format: "sony motion format".to_string(),
+        ver: 1,
+        ver_minor: None,
+    }
+}
+
+fn info() -> Info {
+    Info {
+        addr: Ipv4Addr::UNSPECIFIED.into(),
+        port: 12351,
+    }
+}
+
+fn trans(id: u16) -> Transform {
+    Transform {
+        rot: Rotation::identity(),
+        pos: Position {
+            x: 0.01 * id as f32,
+            y: 0.1,
+            z: 0.0,
+        },
+    }
+}
+
+// mocopiは27本のboneを送る
+fn frame_packet() -> Vec<u8> {
+    encode_frame(&FramePacket {
+        head: head(),
+        info: info(),
+        frame: Frame {
+            num: 1,
+            time: 0,
+            bones: (0..27)
+                .map(|id| BoneTrans {
+                    id,
+                    trans: trans(id),
+                })
+                .collect(),
+        },
+        extra: Vec::new(),
+    })
+    .unwrap()
+}
+
+fn skeleton_packet() -> Vec<u8> {
+    encode_skeleton(&SkeletonPacket {
+        head: head(),
+        info: info(),
+        skeleton: Skeleton {
+            bones: (0..27)
+                .map(|id| Bone {
+                    id,
+                    parent: id.wrapping_sub(1),
+                    trans: trans(id),
+                })
+                .collect(),
+        },
+        extra: Vec::new(),
+    })
+    .unwrap()
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let frame = frame_packet();
+    c.bench_function("parse frame", |b| {
+        b.iter(|| parse(black_box(&frame)).unwrap())
+    });
+
+    let skeleton = skeleton_packet();
+    c.bench_function("parse skeleton", |b| {
+        b.iter(|| parse(black_box(&skeleton)).unwrap())
+    });
+}
+
+fn bench_parse_value(c: &mut Criterion) {
+    let raw = [
+        0x04, 0x00, 0x00, 0x00,
+        0x62, 0x6e, 0x69, 0x64,
+        0x02, 0x00, 0x00, 0x00,
+    ];
+    c.bench_function("parse_value", |b| {
+        b.iter(|| parse_value(black_box(&raw)).unwrap())
+    });
+}
+
+fn bench_parse_trans(c: &mut Criterion) {
+    let raw = [
+        0x1c, 0x00, 0x00, 0x00,
+
+        0x74, 0x72, 0x61, 0x6e,
+
+        0x00, 0x00, 0x9c, 0xa2,
+        0x00, 0xc0, 0xfe, 0xa4,
+        0x00, 0x00, 0xd0, 0xa3,
+        0x00, 0x00, 0x80, 0x3f,
+
+        0x17, 0x56, 0x03, 0xbc,
+        0x7c, 0x48, 0xd0, 0xbd,
+        0x0c, 0xa8, 0x03, 0x3e,
+    ];
+    c.bench_function("parse_trans", |b| {
+        b.iter(|| parse_trans(black_box(&raw)).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_parse, bench_parse_value, bench_parse_trans);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+//! Receive packets from mocopi and print the position of every bone.
+//!
+//! ```sh
+//! cargo run --example listen -- 0.0.0.0:12351
+//! ```
+
+use mocopi_parser::{BoneName, SkeletonOrFrame};
+use std::env;
+use std::net::UdpSocket;
+
+/// The address to bind when none is given, i.e. the default port of mocopi on any interface.
+const DEFAULT_ADDR: &str = "0.0.0.0:12351";
+
+fn main() {
+    let addr = env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_ADDR.to_string());
+    let socket =
+        UdpSocket::bind(&addr).unwrap_or_else(|e| panic!("failed to bind {}: {}", addr, e));
+    println!("listening on {}", addr);
+
+    let mut buf = [0; 2048];
+    loop {
+        let len = match socket.recv(&mut buf) {
+            Ok(len) => len,
+            Err(e) => {
+                eprintln!("failed to receive: {}", e);
+                continue;
+            }
+        };
+
+        match mocopi_parser::parse(&buf[..len]) {
+            Ok(SkeletonOrFrame::Skeleton(packet)) => {
+                println!("skeleton with {} bones", packet.skeleton.bones.len());
+                println!("{}", packet.skeleton);
+            }
+            Ok(SkeletonOrFrame::Frame(packet)) => {
+                println!("frame {} at {} ms", packet.frame.num, packet.frame.time);
+                for bone in &packet.frame.bones {
+                    let pos = &bone.trans.pos;
+                    let name = BoneName::try_from(bone.id).map_or("unknown", |name| name.name());
+                    println!(
+                        "  {:>2} {:<16} {:>9.5} {:>9.5} {:>9.5}",
+                        bone.id, name, pos.x, pos.y, pos.z
+                    );
+                }
+            }
+            Err(e) => eprintln!("failed to parse: {}", e),
+        }
+    }
+}
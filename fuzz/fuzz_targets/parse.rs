@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// どんな入力でもpanicせずにErrを返すこと
+fuzz_target!(|data: &[u8]| {
+    let _ = mocopi_parser::parse(data);
+});
@@ -0,0 +1,139 @@
+use crate::BoneId;
+use serde::{Deserialize, Serialize};
+
+/// The names of the 27 bones in the mocopi skeleton.
+///
+/// The discriminant of each variant is the bone ID sent by mocopi.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u16)]
+pub enum BoneName {
+    Root = 0,
+    Torso1 = 1,
+    Torso2 = 2,
+    Torso3 = 3,
+    Torso4 = 4,
+    Torso5 = 5,
+    Torso6 = 6,
+    Torso7 = 7,
+    Neck1 = 8,
+    Neck2 = 9,
+    Head = 10,
+    LShoulder = 11,
+    LUpArm = 12,
+    LLowArm = 13,
+    LHand = 14,
+    RShoulder = 15,
+    RUpArm = 16,
+    RLowArm = 17,
+    RHand = 18,
+    LUpLeg = 19,
+    LLowLeg = 20,
+    LFoot = 21,
+    LToes = 22,
+    RUpLeg = 23,
+    RLowLeg = 24,
+    RFoot = 25,
+    RToes = 26,
+}
+
+impl BoneName {
+    /// All the bones in the order of their IDs.
+    pub const ALL: [BoneName; 27] = [
+        BoneName::Root,
+        BoneName::Torso1,
+        BoneName::Torso2,
+        BoneName::Torso3,
+        BoneName::Torso4,
+        BoneName::Torso5,
+        BoneName::Torso6,
+        BoneName::Torso7,
+        BoneName::Neck1,
+        BoneName::Neck2,
+        BoneName::Head,
+        BoneName::LShoulder,
+        BoneName::LUpArm,
+        BoneName::LLowArm,
+        BoneName::LHand,
+        BoneName::RShoulder,
+        BoneName::RUpArm,
+        BoneName::RLowArm,
+        BoneName::RHand,
+        BoneName::LUpLeg,
+        BoneName::LLowLeg,
+        BoneName::LFoot,
+        BoneName::LToes,
+        BoneName::RUpLeg,
+        BoneName::RLowLeg,
+        BoneName::RFoot,
+        BoneName::RToes,
+    ];
+
+    /// The bone ID sent by mocopi.
+    pub fn id(&self) -> BoneId {
+        *self as BoneId
+    }
+
+    /// The name of the bone used by mocopi.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BoneName::Root => "root",
+            BoneName::Torso1 => "torso_1",
+            BoneName::Torso2 => "torso_2",
+            BoneName::Torso3 => "torso_3",
+            BoneName::Torso4 => "torso_4",
+            BoneName::Torso5 => "torso_5",
+            BoneName::Torso6 => "torso_6",
+            BoneName::Torso7 => "torso_7",
+            BoneName::Neck1 => "neck_1",
+            BoneName::Neck2 => "neck_2",
+            BoneName::Head => "head",
+            BoneName::LShoulder => "l_shoulder",
+            BoneName::LUpArm => "l_up_arm",
+            BoneName::LLowArm => "l_low_arm",
+            BoneName::LHand => "l_hand",
+            BoneName::RShoulder => "r_shoulder",
+            BoneName::RUpArm => "r_up_arm",
+            BoneName::RLowArm => "r_low_arm",
+            BoneName::RHand => "r_hand",
+            BoneName::LUpLeg => "l_up_leg",
+            BoneName::LLowLeg => "l_low_leg",
+            BoneName::LFoot => "l_foot",
+            BoneName::LToes => "l_toes",
+            BoneName::RUpLeg => "r_up_leg",
+            BoneName::RLowLeg => "r_low_leg",
+            BoneName::RFoot => "r_foot",
+            BoneName::RToes => "r_toes",
+        }
+    }
+}
+
+impl TryFrom<BoneId> for BoneName {
+    /// The ID which does not belong to the mocopi skeleton.
+    type Error = BoneId;
+
+    fn try_from(id: BoneId) -> Result<Self, Self::Error> {
+        BoneName::ALL.get(id as usize).copied().ok_or(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bone_name_order() {
+        for (i, name) in BoneName::ALL.iter().enumerate() {
+            assert_eq!(name.id(), i as BoneId);
+            assert_eq!(BoneName::try_from(i as BoneId), Ok(*name));
+        }
+
+        assert_eq!(BoneName::try_from(0), Ok(BoneName::Root));
+        assert_eq!(BoneName::try_from(10), Ok(BoneName::Head));
+        assert_eq!(BoneName::try_from(14), Ok(BoneName::LHand));
+        assert_eq!(BoneName::try_from(26), Ok(BoneName::RToes));
+        assert_eq!(BoneName::try_from(27), Err(27));
+
+        assert_eq!(BoneName::Head.name(), "head");
+        assert_eq!(BoneName::RLowLeg.name(), "r_low_leg");
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{
+    Bone, BoneId, Head, Info, Skeleton, SkeletonError, SkeletonPacket, Transform, EXPECTED_FORMAT,
+};
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::net::Ipv4Addr;
+
+/// A builder of [`SkeletonPacket`]s.
+///
+/// Unless set, the head has [`EXPECTED_FORMAT`] and version 1, and the info has the
+/// unspecified address and port 12351, which mocopi uses by default.
+///
+/// # Examples
+///
+/// ```
+/// use mocopi_parser::{SkeletonBuilder, Transform, ROOT_PARENT};
+///
+/// let packet = SkeletonBuilder::new()
+///     .add_bone(0, ROOT_PARENT, Transform::identity())
+///     .add_bone(1, 0, Transform::identity())
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(packet.skeleton.bones.len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SkeletonBuilder {
+    head: Head,
+    info: Info,
+    bones: Vec<Bone>,
+}
+
+impl SkeletonBuilder {
+    /// Create a builder with no bones.
+    pub fn new() -> Self {
+        SkeletonBuilder {
+            head: Head {
+                format: EXPECTED_FORMAT.to_string(),
+                ver: 1,
+                ver_minor: None,
+            },
+            info: Info {
+                addr: Ipv4Addr::UNSPECIFIED.into(),
+                port: 12351,
+            },
+            bones: Vec::new(),
+        }
+    }
+
+    /// Set the head of the packet.
+    pub fn head(mut self, head: Head) -> Self {
+        self.head = head;
+        self
+    }
+
+    /// Set the info of the packet.
+    pub fn info(mut self, info: Info) -> Self {
+        self.info = info;
+        self
+    }
+
+    /// Add a bone. The bones are kept in the order they are added.
+    pub fn add_bone(mut self, id: BoneId, parent: BoneId, trans: Transform) -> Self {
+        self.bones.push(Bone { id, parent, trans });
+        self
+    }
+
+    /// Build the packet, checking the bones with [`Skeleton::validate`].
+    pub fn build(self) -> Result<SkeletonPacket, SkeletonError> {
+        let skeleton = Skeleton { bones: self.bones };
+        skeleton.validate()?;
+
+        Ok(SkeletonPacket {
+            head: self.head,
+            info: self.info,
+            skeleton,
+            extra: Vec::new(),
+        })
+    }
+}
+
+impl Default for SkeletonBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode_skeleton, parse, Position, ROOT_PARENT};
+    use alloc::vec;
+
+    fn trans(y: f32) -> Transform {
+        Transform {
+            pos: Position { x: 0.0, y, z: 0.0 },
+            ..Transform::identity()
+        }
+    }
+
+    #[test]
+    fn test_build() {
+        let packet = SkeletonBuilder::new()
+            .info(Info {
+                addr: Ipv4Addr::new(192, 168, 10, 2).into(),
+                port: 12351,
+            })
+            .add_bone(0, ROOT_PARENT, trans(0.0))
+            .add_bone(1, 0, trans(0.5))
+            .add_bone(2, 1, trans(0.25))
+            .build()
+            .unwrap();
+
+        let raw = encode_skeleton(&packet).unwrap();
+        let parsed = parse(&raw).unwrap();
+
+        assert_eq!(parsed.as_skeleton(), Some(&packet));
+    }
+
+    #[test]
+    fn test_build_duplicate() {
+        let err = SkeletonBuilder::new()
+            .add_bone(0, ROOT_PARENT, trans(0.0))
+            .add_bone(1, 0, trans(0.5))
+            .add_bone(1, 0, trans(0.25))
+            .build()
+            .unwrap_err();
+
+        assert_eq!(err, SkeletonError::DuplicateIds(vec![1]));
+    }
+}
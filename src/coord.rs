@@ -0,0 +1,152 @@
+//! Conversions between coordinate systems.
+//!
+//! mocopi sends transforms in the coordinate system of Unity: Y is up and the system is
+//! left-handed.
+
+use crate::{Position, Rotation, Transform};
+
+impl Transform {
+    /// Convert from a Y-up to a Z-up coordinate system with the same handedness.
+    ///
+    /// The axes are rotated so that +Y becomes +Z and +Z becomes -Y.
+    pub fn to_z_up(&self) -> Transform {
+        Transform {
+            rot: Rotation {
+                x: self.rot.x,
+                y: -self.rot.z,
+                z: self.rot.y,
+                w: self.rot.w,
+            },
+            pos: Position {
+                x: self.pos.x,
+                y: -self.pos.z,
+                z: self.pos.y,
+            },
+        }
+    }
+
+    /// Convert from a Z-up to a Y-up coordinate system with the same handedness.
+    ///
+    /// This is the inverse of [`Transform::to_z_up`].
+    pub fn to_y_up(&self) -> Transform {
+        Transform {
+            rot: Rotation {
+                x: self.rot.x,
+                y: self.rot.z,
+                z: -self.rot.y,
+                w: self.rot.w,
+            },
+            pos: Position {
+                x: self.pos.x,
+                y: self.pos.z,
+                z: -self.pos.y,
+            },
+        }
+    }
+
+    /// Convert between left-handed and right-handed coordinate systems by negating Z.
+    ///
+    /// Mirroring turns the rotations around, so X and Y of the quaternion are negated.
+    pub fn flip_handedness(&self) -> Transform {
+        Transform {
+            rot: Rotation {
+                x: -self.rot.x,
+                y: -self.rot.y,
+                z: self.rot.z,
+                w: self.rot.w,
+            },
+            pos: Position {
+                x: self.pos.x,
+                y: self.pos.y,
+                z: -self.pos.z,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::FRAC_1_SQRT_2;
+
+    fn assert_near(a: Position, b: Position) {
+        assert!((a.x - b.x).abs() < 1e-6, "{:?} {:?}", a, b);
+        assert!((a.y - b.y).abs() < 1e-6, "{:?} {:?}", a, b);
+        assert!((a.z - b.z).abs() < 1e-6, "{:?} {:?}", a, b);
+    }
+
+    fn trans() -> Transform {
+        // Y軸まわりに90°回転
+        Transform {
+            rot: Rotation {
+                x: 0.0,
+                y: FRAC_1_SQRT_2,
+                z: 0.0,
+                w: FRAC_1_SQRT_2,
+            },
+            pos: Position {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_to_z_up() {
+        let z_up = trans().to_z_up();
+
+        // 上向きの軸まわりの回転はZ軸まわりになる
+        assert_eq!(
+            z_up.rot,
+            Rotation {
+                x: 0.0,
+                y: 0.0,
+                z: FRAC_1_SQRT_2,
+                w: FRAC_1_SQRT_2,
+            }
+        );
+        assert_eq!(
+            z_up.pos,
+            Position {
+                x: 1.0,
+                y: -3.0,
+                z: 2.0,
+            }
+        );
+        assert_eq!(z_up.to_y_up(), trans());
+
+        // 変換してから回転しても、回転してから変換しても同じになる
+        let p = Position {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let rotated = Transform {
+            pos: trans().rot.rotate(p),
+            ..Transform::identity()
+        };
+        let converted = Transform {
+            pos: p,
+            ..Transform::identity()
+        }
+        .to_z_up();
+        assert_near(z_up.rot.rotate(converted.pos), rotated.to_z_up().pos);
+    }
+
+    #[test]
+    fn test_flip_handedness() {
+        let flipped = trans().flip_handedness();
+
+        assert_eq!(flipped.pos.z, -3.0);
+        assert_eq!(flipped.flip_handedness(), trans());
+
+        let p = Position {
+            x: 1.0,
+            y: 0.5,
+            z: 0.0,
+        };
+        let mirror = |p: Position| Position { z: -p.z, ..p };
+        assert_near(flipped.rot.rotate(mirror(p)), mirror(trans().rot.rotate(p)));
+    }
+}
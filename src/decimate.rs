@@ -0,0 +1,163 @@
+use crate::Frame;
+
+/// Drop frames to reduce the frame rate, the opposite of a [`Resampler`](crate::Resampler).
+///
+/// The kept frames are passed through unchanged. With [`Decimator::max_fps`], the frames are
+/// kept at even times by their `time`, which may wrap around, and a frame which is older than
+/// the last kept one, e.g. a reordered datagram, is dropped.
+#[derive(Debug, Clone)]
+pub struct Decimator {
+    mode: Mode,
+    /// The time of the last kept frame.
+    last: Option<u32>,
+    /// The number of frames dropped since the last kept one.
+    dropped: u32,
+    /// How many milliseconds the last kept frame was after its time slot.
+    late: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Every(u32),
+    /// The interval between the kept frames in milliseconds.
+    Interval(f64),
+}
+
+impl Decimator {
+    /// Create a decimator which keeps the first frame and every `n`th frame after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0.
+    pub fn every(n: u32) -> Self {
+        assert!(n > 0, "n must be positive");
+        Self::with_mode(Mode::Every(n))
+    }
+
+    /// Create a decimator which keeps at most `fps` frames per second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fps` is not a positive finite number.
+    pub fn max_fps(fps: f32) -> Self {
+        assert!(
+            fps > 0.0 && fps.is_finite(),
+            "fps must be positive and finite"
+        );
+        Self::with_mode(Mode::Interval(1000.0 / fps as f64))
+    }
+
+    fn with_mode(mode: Mode) -> Self {
+        Decimator {
+            mode,
+            last: None,
+            dropped: 0,
+            late: 0.0,
+        }
+    }
+
+    /// Add a frame and return it if it is kept.
+    pub fn push(&mut self, frame: Frame) -> Option<Frame> {
+        match self.mode {
+            Mode::Every(n) => {
+                // 最初のframeも残す
+                if self.last.is_some() && self.dropped + 1 < n {
+                    self.dropped += 1;
+                    return None;
+                }
+                self.dropped = 0;
+            }
+            Mode::Interval(interval) => {
+                if let Some(last) = self.last {
+                    // 差が半分を超えるときは古いframeとみなす
+                    let millis = frame.time.wrapping_sub(last);
+                    if millis > u32::MAX / 2 {
+                        return None;
+                    }
+                    let elapsed = self.late + millis as f64;
+                    if elapsed < interval {
+                        return None;
+                    }
+                    // 遅れて残したframeの分だけ次を早め、間隔が揃うようにする
+                    self.late = elapsed % interval;
+                }
+            }
+        }
+        self.last = Some(frame.time);
+        Some(frame)
+    }
+
+    /// Forget the frames so far, so that the next frame is kept.
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.dropped = 0;
+        self.late = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn frame(num: u32, time: u32) -> Frame {
+        Frame {
+            num,
+            time,
+            bones: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_max_fps() {
+        let mut decimator = Decimator::max_fps(20.0);
+
+        // 60fpsで1秒分
+        let kept: Vec<Frame> = (0..60)
+            .filter_map(|num| {
+                let time = (num as f32 * 1000.0 / 60.0).round() as u32;
+                decimator.push(frame(num, time))
+            })
+            .collect();
+
+        assert_eq!(kept.len(), 20);
+        for (i, frame) in kept.iter().enumerate() {
+            assert_eq!(frame.num, i as u32 * 3);
+            assert_eq!(frame.time, i as u32 * 50);
+        }
+
+        // 遅れて届いたframeは残さない
+        assert!(decimator.push(frame(56, 940)).is_none());
+        // 間が空いても、次の時間に届いたframeは残す
+        assert_eq!(decimator.push(frame(70, 1170)).unwrap().num, 70);
+        assert!(decimator.push(frame(71, 1190)).is_none());
+        assert_eq!(decimator.push(frame(72, 1200)).unwrap().num, 72);
+    }
+
+    #[test]
+    fn test_max_fps_wraparound() {
+        let mut decimator = Decimator::max_fps(10.0);
+        assert!(decimator.push(frame(0, u32::MAX - 50)).is_some());
+        assert!(decimator.push(frame(1, u32::MAX)).is_none());
+        assert!(decimator.push(frame(2, 49)).is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "fps must be positive and finite")]
+    fn test_max_fps_nan() {
+        Decimator::max_fps(f32::NAN);
+    }
+
+    #[test]
+    fn test_every() {
+        let mut decimator = Decimator::every(3);
+        let kept: Vec<u32> = (0..10)
+            .filter_map(|num| decimator.push(frame(num, num * 10)))
+            .map(|frame| frame.num)
+            .collect();
+        assert_eq!(kept, [0, 3, 6, 9]);
+
+        decimator.reset();
+        assert!(decimator.push(frame(10, 100)).is_some());
+    }
+}
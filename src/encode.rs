@@ -0,0 +1,326 @@
+//! Encode packets into the binary format sent by mocopi.
+
+use crate::{
+    is_extra_section, tags, Bone, BoneTrans, EncodeError, FramePacket, Head, Info, ParseOptions,
+    SkeletonOrFrame, SkeletonPacket, Transform,
+};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::net::IpAddr;
+
+/// Write a value with its length and name, filling the data with `f`.
+fn write_value(buf: &mut Vec<u8>, name: &str, f: impl FnOnce(&mut Vec<u8>)) {
+    let start = buf.len();
+    buf.extend_from_slice(&[0; 4]);
+    buf.extend_from_slice(name.as_bytes());
+
+    f(buf);
+
+    // lengthにはnameの後ろのデータの長さを入れる
+    let len = (buf.len() - start - 8) as u32;
+    buf[start..start + 4].copy_from_slice(&len.to_le_bytes());
+}
+
+fn write_head(buf: &mut Vec<u8>, head: &Head) {
+    write_value(buf, tags::HEAD, |buf| {
+        write_value(buf, tags::FTYP, |buf| {
+            buf.extend_from_slice(head.format.as_bytes())
+        });
+        write_value(buf, tags::VRSN, |buf| {
+            buf.push(head.ver);
+            buf.extend(head.ver_minor);
+        });
+    });
+}
+
+fn write_info(buf: &mut Vec<u8>, info: &Info) {
+    write_value(buf, tags::SNDF, |buf| {
+        write_value(buf, tags::IPAD, |buf| match info.addr {
+            // mocopiはIPv4のアドレスを8bytesで送る
+            IpAddr::V4(addr) => {
+                buf.extend_from_slice(&addr.octets());
+                buf.extend_from_slice(&[0; 4]);
+            }
+            IpAddr::V6(addr) => buf.extend_from_slice(&addr.octets()),
+        });
+        write_value(buf, tags::RCVP, |buf| {
+            buf.extend_from_slice(&info.port.to_le_bytes())
+        });
+    });
+}
+
+/// Check that the extra sections would be parsed back as they are.
+fn check_extra(extra: &[(String, Vec<u8>)]) -> Result<(), EncodeError> {
+    match extra
+        .iter()
+        .find(|(name, _)| name.len() != 4 || !is_extra_section(name, &ParseOptions::default()))
+    {
+        Some((name, _)) => Err(EncodeError::InvalidSectionName(name.clone())),
+        None => Ok(()),
+    }
+}
+
+fn write_extra(buf: &mut Vec<u8>, extra: &[(String, Vec<u8>)]) {
+    for (name, data) in extra {
+        write_value(buf, name, |buf| buf.extend_from_slice(data));
+    }
+}
+
+fn write_trans(buf: &mut Vec<u8>, trans: &Transform) {
+    write_value(buf, tags::TRAN, |buf| {
+        let values = [
+            trans.rot.x,
+            trans.rot.y,
+            trans.rot.z,
+            trans.rot.w,
+            trans.pos.x,
+            trans.pos.y,
+            trans.pos.z,
+        ];
+        for v in values {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+    });
+}
+
+fn write_bone(buf: &mut Vec<u8>, bone: &Bone) {
+    write_value(buf, tags::BNDT, |buf| {
+        write_value(buf, tags::BNID, |buf| {
+            buf.extend_from_slice(&bone.id.to_le_bytes())
+        });
+        write_value(buf, tags::PBID, |buf| {
+            buf.extend_from_slice(&bone.parent.to_le_bytes())
+        });
+        write_trans(buf, &bone.trans);
+    });
+}
+
+fn write_bone_trans(buf: &mut Vec<u8>, bone: &BoneTrans) {
+    write_value(buf, tags::BTDT, |buf| {
+        write_value(buf, tags::BNID, |buf| {
+            buf.extend_from_slice(&bone.id.to_le_bytes())
+        });
+        write_trans(buf, &bone.trans);
+    });
+}
+
+/// Encode a skeleton packet into the bytes which [`parse`](crate::parse) consumes.
+///
+/// Returns [`EncodeError::InvalidSectionName`] if the name of an extra section is not 4 ASCII
+/// alphanumeric bytes, or it is `head`.
+pub fn encode_skeleton(packet: &SkeletonPacket) -> Result<Vec<u8>, EncodeError> {
+    check_extra(&packet.extra)?;
+    let mut buf = Vec::new();
+    write_head(&mut buf, &packet.head);
+    write_info(&mut buf, &packet.info);
+    write_value(&mut buf, tags::SKDF, |buf| {
+        write_value(buf, tags::BONS, |buf| {
+            for bone in &packet.skeleton.bones {
+                write_bone(buf, bone);
+            }
+        });
+    });
+    write_extra(&mut buf, &packet.extra);
+    Ok(buf)
+}
+
+/// Encode a frame packet into the bytes which [`parse`](crate::parse) consumes.
+///
+/// Returns [`EncodeError::InvalidSectionName`] like [`encode_skeleton`].
+pub fn encode_frame(packet: &FramePacket) -> Result<Vec<u8>, EncodeError> {
+    check_extra(&packet.extra)?;
+    let mut buf = Vec::new();
+    write_head(&mut buf, &packet.head);
+    write_info(&mut buf, &packet.info);
+    write_value(&mut buf, tags::FRAM, |buf| {
+        let frame = &packet.frame;
+        write_value(buf, tags::FNUM, |buf| {
+            buf.extend_from_slice(&frame.num.to_le_bytes())
+        });
+        write_value(buf, tags::TIME, |buf| {
+            buf.extend_from_slice(&frame.time.to_le_bytes())
+        });
+        write_value(buf, tags::BTRS, |buf| {
+            for bone in &frame.bones {
+                write_bone_trans(buf, bone);
+            }
+        });
+    });
+    write_extra(&mut buf, &packet.extra);
+    Ok(buf)
+}
+
+/// The length of a value whose data is `len` bytes long.
+const fn value_len(len: usize) -> usize {
+    8 + len
+}
+
+const TRANS_LEN: usize = value_len(28);
+
+/// The number of bytes which [`encode_skeleton`] or [`encode_frame`] would produce for the
+/// packet.
+pub fn encoded_len(packet: &SkeletonOrFrame) -> usize {
+    let (head, info, extra) = match packet {
+        SkeletonOrFrame::Skeleton(packet) => (&packet.head, &packet.info, &packet.extra),
+        SkeletonOrFrame::Frame(packet) => (&packet.head, &packet.info, &packet.extra),
+    };
+    let head_len =
+        value_len(value_len(head.format.len()) + value_len(1 + head.ver_minor.iter().len()));
+    let addr_len = match info.addr {
+        IpAddr::V4(_) => 8,
+        IpAddr::V6(_) => 16,
+    };
+    let info_len = value_len(value_len(addr_len) + value_len(2));
+
+    let body_len = match packet {
+        SkeletonOrFrame::Skeleton(packet) => {
+            // bndtはbnid, pbid, tranを持つ
+            let bone_len = value_len(value_len(2) + value_len(2) + TRANS_LEN);
+            value_len(value_len(bone_len * packet.skeleton.bones.len()))
+        }
+        SkeletonOrFrame::Frame(packet) => {
+            // btdtはbnid, tranを持つ
+            let bone_len = value_len(value_len(2) + TRANS_LEN);
+            value_len(value_len(4) + value_len(4) + value_len(bone_len * packet.frame.bones.len()))
+        }
+    };
+
+    let extra_len: usize = extra.iter().map(|(_, data)| value_len(data.len())).sum();
+
+    head_len + info_len + body_len + extra_len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{head, info};
+    use crate::{parse, Frame, Position, Rotation, Skeleton, SkeletonOrFrame};
+    use alloc::string::ToString;
+    use alloc::vec;
+    use core::net::Ipv6Addr;
+
+    fn trans(i: u16) -> Transform {
+        let v = i as f32;
+        Transform {
+            rot: Rotation {
+                x: 0.1 * v,
+                y: 0.2 * v,
+                z: 0.3 * v,
+                w: 1.0,
+            },
+            pos: Position {
+                x: v,
+                y: -v,
+                z: 0.5 * v,
+            },
+        }
+    }
+
+    #[test]
+    fn test_encode_skeleton() {
+        let bones = (0..3)
+            .map(|id| Bone {
+                id,
+                parent: id.wrapping_sub(1),
+                trans: trans(id),
+            })
+            .collect();
+        let packet = SkeletonPacket {
+            head: head(),
+            info: info(),
+            skeleton: Skeleton { bones },
+            extra: Vec::new(),
+        };
+
+        let raw = encode_skeleton(&packet).unwrap();
+
+        match parse(&raw).unwrap() {
+            SkeletonOrFrame::Skeleton(parsed) => assert_eq!(parsed, packet),
+            SkeletonOrFrame::Frame(_) => panic!("expected a skeleton packet"),
+        }
+        assert_eq!(encoded_len(&SkeletonOrFrame::Skeleton(packet)), raw.len());
+    }
+
+    #[test]
+    fn test_encode_frame() {
+        let bones = (0..3)
+            .map(|id| BoneTrans {
+                id,
+                trans: trans(id),
+            })
+            .collect();
+        let packet = FramePacket {
+            head: Head {
+                ver_minor: Some(2),
+                ..head()
+            },
+            info: info(),
+            frame: Frame {
+                num: 42,
+                time: 1000,
+                bones,
+            },
+            extra: vec![("xtra".to_string(), vec![0x01, 0x02, 0x03])],
+        };
+
+        let raw = encode_frame(&packet).unwrap();
+
+        match parse(&raw).unwrap() {
+            SkeletonOrFrame::Frame(parsed) => assert_eq!(parsed, packet),
+            SkeletonOrFrame::Skeleton(_) => panic!("expected a frame packet"),
+        }
+        assert_eq!(encoded_len(&SkeletonOrFrame::Frame(packet)), raw.len());
+    }
+
+    #[test]
+    fn test_encode_ipv6() {
+        let packet = FramePacket {
+            head: head(),
+            info: Info {
+                addr: Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1).into(),
+                port: 12351,
+            },
+            frame: Frame {
+                num: 1,
+                time: 20,
+                bones: vec![BoneTrans {
+                    id: 0,
+                    trans: trans(0),
+                }],
+            },
+            extra: Vec::new(),
+        };
+
+        let raw = encode_frame(&packet).unwrap();
+        assert_eq!(
+            encoded_len(&SkeletonOrFrame::Frame(packet.clone())),
+            raw.len()
+        );
+
+        match parse(&raw).unwrap() {
+            SkeletonOrFrame::Frame(parsed) => assert_eq!(parsed, packet),
+            SkeletonOrFrame::Skeleton(_) => panic!("expected a frame packet"),
+        }
+    }
+
+    #[test]
+    fn test_encode_invalid_extra() {
+        for name in ["toolong", "xtr", "xt a", "head", ""] {
+            let packet = FramePacket {
+                head: head(),
+                info: info(),
+                frame: Frame {
+                    num: 1,
+                    time: 20,
+                    bones: Vec::new(),
+                },
+                extra: vec![(name.to_string(), vec![0x01])],
+            };
+
+            assert_eq!(
+                encode_frame(&packet),
+                Err(EncodeError::InvalidSectionName(name.to_string()))
+            );
+        }
+    }
+}
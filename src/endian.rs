@@ -0,0 +1,46 @@
+use crate::ParseError;
+
+/// The byte order of the numbers in a packet.
+///
+/// mocopi sends little-endian packets, but some firmware is reported to send big-endian ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endian {
+    #[default]
+    Little,
+    Big,
+}
+
+impl Endian {
+    pub(crate) fn u16(self, data: &[u8]) -> Result<u16, ParseError> {
+        let bytes = data.try_into()?;
+        Ok(match self {
+            Endian::Little => u16::from_le_bytes(bytes),
+            Endian::Big => u16::from_be_bytes(bytes),
+        })
+    }
+
+    pub(crate) fn u32(self, data: &[u8]) -> Result<u32, ParseError> {
+        let bytes = data.try_into()?;
+        Ok(match self {
+            Endian::Little => u32::from_le_bytes(bytes),
+            Endian::Big => u32::from_be_bytes(bytes),
+        })
+    }
+
+    pub(crate) fn f32(self, data: &[u8]) -> Result<f32, ParseError> {
+        let bytes = data.try_into()?;
+        Ok(match self {
+            Endian::Little => f32::from_le_bytes(bytes),
+            Endian::Big => f32::from_be_bytes(bytes),
+        })
+    }
+}
+
+impl From<Endian> for nom::number::Endianness {
+    fn from(endian: Endian) -> Self {
+        match endian {
+            Endian::Little => nom::number::Endianness::Little,
+            Endian::Big => nom::number::Endianness::Big,
+        }
+    }
+}
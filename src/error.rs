@@ -0,0 +1,320 @@
+use crate::BoneId;
+use alloc::string::{FromUtf8Error, String};
+use alloc::vec::Vec;
+use core::array::TryFromSliceError;
+use core::fmt;
+use core::str::Utf8Error;
+pub use nom::Needed;
+#[cfg(feature = "std")]
+use std::error;
+#[cfg(feature = "std")]
+use std::io;
+
+/// An error which can be returned when parsing the streamed data from mocopi.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The data ended before the expected number of bytes could be read.
+    ///
+    /// `offset` is where the value being read starts, counted from the start of the packet,
+    /// like in the other variants which have it.
+    UnexpectedEof {
+        offset: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// The length of a value is longer than the remaining data, e.g. the data is only a part
+    /// of a packet.
+    Truncated {
+        offset: usize,
+        needed: usize,
+        available: usize,
+    },
+    /// A value has a different name from the one expected at its position.
+    UnexpectedTag {
+        offset: usize,
+        expected: &'static str,
+        found: String,
+    },
+    /// A name or a string value is not valid UTF-8.
+    InvalidUtf8(Utf8Error),
+    /// A value does not have the length expected for its type.
+    BadLength,
+    /// The address in the info is neither 4, 8 nor 16 bytes long.
+    BadAddrLen(usize),
+    /// The format in the head is not [`EXPECTED_FORMAT`](crate::EXPECTED_FORMAT).
+    UnknownFormat(String),
+    /// The number of bones differs from the number of sections declared by the packet.
+    BoneCountMismatch { declared: usize, parsed: usize },
+    /// A skeleton or a frame has more bones than the limit given to the parser.
+    TooManyBones { max: usize },
+    /// The version in the head is not one which this crate can parse.
+    UnsupportedVersion(u8),
+    /// The packet is neither a skeleton nor a frame.
+    UnknownPacketType(String),
+    /// The data ended in the middle of a packet, returned by
+    /// [`parse_streaming`](crate::parse_streaming) with the number of bytes still needed.
+    Incomplete(Needed),
+    /// Bytes are left after the packet, returned by [`parse_exact`](crate::parse_exact).
+    TrailingBytes(usize),
+    /// The buffered bytes exceeded the capacity of the buffer.
+    BufferOverflow { capacity: usize },
+    /// The stream ended before a packet started.
+    EndOfStream,
+    /// The stream ended in the middle of a packet.
+    StreamTruncated,
+    /// Reading from the stream failed.
+    #[cfg(feature = "std")]
+    Io(io::ErrorKind),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedEof {
+                offset,
+                expected,
+                found,
+            } => write!(
+                f,
+                "unexpected end of data at byte {}: expected {} bytes, found {}",
+                offset, expected, found
+            ),
+            ParseError::Truncated {
+                offset,
+                needed,
+                available,
+            } => write!(
+                f,
+                "value at byte {} is truncated: needed {} bytes, {} available",
+                offset, needed, available
+            ),
+            ParseError::UnexpectedTag {
+                offset,
+                expected,
+                found,
+            } => write!(
+                f,
+                "unexpected tag at byte {}: expected {}, found {}",
+                offset, expected, found
+            ),
+            ParseError::InvalidUtf8(e) => write!(f, "invalid utf-8: {}", e),
+            ParseError::BadLength => write!(f, "value has an unexpected length"),
+            ParseError::BadAddrLen(len) => write!(f, "address has an unexpected length: {}", len),
+            ParseError::UnknownFormat(format) => write!(f, "unknown format: {}", format),
+            ParseError::UnknownPacketType(name) => write!(f, "unknown packet type: {}", name),
+            ParseError::Incomplete(Needed::Size(len)) => {
+                write!(f, "incomplete data: {} more bytes needed", len)
+            }
+            ParseError::Incomplete(Needed::Unknown) => write!(f, "incomplete data"),
+            ParseError::TrailingBytes(len) => write!(f, "{} bytes left after the packet", len),
+            ParseError::BufferOverflow { capacity } => {
+                write!(f, "buffer exceeded its capacity of {} bytes", capacity)
+            }
+            ParseError::BoneCountMismatch { declared, parsed } => {
+                write!(f, "packet declares {} bones, found {}", declared, parsed)
+            }
+            ParseError::TooManyBones { max } => write!(f, "more than {} bones", max),
+            ParseError::UnsupportedVersion(ver) => write!(f, "unsupported version: {}", ver),
+            ParseError::EndOfStream => write!(f, "end of stream"),
+            ParseError::StreamTruncated => write!(f, "stream ended in the middle of a packet"),
+            #[cfg(feature = "std")]
+            ParseError::Io(kind) => write!(f, "failed to read the stream: {}", kind),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ParseError::InvalidUtf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Utf8Error> for ParseError {
+    fn from(e: Utf8Error) -> Self {
+        ParseError::InvalidUtf8(e)
+    }
+}
+
+impl From<FromUtf8Error> for ParseError {
+    fn from(e: FromUtf8Error) -> Self {
+        ParseError::InvalidUtf8(e.utf8_error())
+    }
+}
+
+impl From<TryFromSliceError> for ParseError {
+    fn from(_: TryFromSliceError) -> Self {
+        ParseError::BadLength
+    }
+}
+
+/// An error which can be returned when the hierarchy of a skeleton is malformed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkeletonError {
+    /// The bones refer to parents which do not exist.
+    DanglingParents(Vec<BoneId>),
+    /// The bones form a cycle.
+    Cycle(Vec<BoneId>),
+    /// More than one bone has each of these IDs.
+    DuplicateIds(Vec<BoneId>),
+}
+
+impl fmt::Display for SkeletonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkeletonError::DanglingParents(ids) => {
+                write!(f, "bones refer to missing parents: {:?}", ids)
+            }
+            SkeletonError::Cycle(ids) => write!(f, "bones form a cycle: {:?}", ids),
+            SkeletonError::DuplicateIds(ids) => write!(f, "bone IDs are duplicated: {:?}", ids),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for SkeletonError {}
+
+/// An error which can be returned when exporting packets into another format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportError {
+    /// The skeleton has no root bone.
+    NoRoot,
+    /// The hierarchy of the skeleton is malformed.
+    Skeleton(SkeletonError),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::NoRoot => write!(f, "skeleton has no root bone"),
+            ExportError::Skeleton(e) => write!(f, "invalid skeleton: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for ExportError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ExportError::Skeleton(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// An error which can be returned when encoding a packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The name of an extra section is not 4 ASCII alphanumeric bytes, or it is `head`, so the
+    /// section could not be parsed back.
+    InvalidSectionName(String),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::InvalidSectionName(name) => write!(f, "invalid section name: {}", name),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for EncodeError {}
+
+/// An error which can be returned when importing a skeleton from another format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportError {
+    /// A token is not the one expected at its position. `line` counts from 1.
+    UnexpectedToken { line: usize, found: String },
+    /// The data ended in the middle of the skeleton.
+    UnexpectedEnd,
+    /// A bone has a name which is neither a mocopi bone nor `bone_<id>`.
+    UnknownBone(String),
+    /// The hierarchy of the skeleton is malformed, e.g. a bone appears twice.
+    Skeleton(SkeletonError),
+    /// The joints are nested more than `max` deep.
+    TooDeep { max: usize },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::UnexpectedToken { line, found } => {
+                write!(f, "unexpected token at line {}: {}", line, found)
+            }
+            ImportError::UnexpectedEnd => write!(f, "unexpected end of data"),
+            ImportError::UnknownBone(name) => write!(f, "unknown bone: {}", name),
+            ImportError::Skeleton(e) => write!(f, "invalid skeleton: {}", e),
+            ImportError::TooDeep { max } => write!(f, "joints nested more than {} deep", max),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ImportError::Skeleton(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// An error which can be returned when logging to Rerun.
+#[cfg(feature = "rerun")]
+#[derive(Debug)]
+pub enum LogError {
+    /// The hierarchy of the skeleton is malformed.
+    Skeleton(SkeletonError),
+    /// Rerun failed to log.
+    Rerun(rerun::RecordingStreamError),
+}
+
+#[cfg(feature = "rerun")]
+impl fmt::Display for LogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogError::Skeleton(e) => write!(f, "invalid skeleton: {}", e),
+            LogError::Rerun(e) => write!(f, "failed to log: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "rerun")]
+impl error::Error for LogError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            LogError::Skeleton(e) => Some(e),
+            LogError::Rerun(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(feature = "rerun")]
+impl From<SkeletonError> for LogError {
+    fn from(e: SkeletonError) -> Self {
+        LogError::Skeleton(e)
+    }
+}
+
+#[cfg(feature = "rerun")]
+impl From<rerun::RecordingStreamError> for LogError {
+    fn from(e: rerun::RecordingStreamError) -> Self {
+        LogError::Rerun(e)
+    }
+}
+
+impl From<SkeletonError> for ExportError {
+    fn from(e: SkeletonError) -> Self {
+        ExportError::Skeleton(e)
+    }
+}
+
+impl From<SkeletonError> for ImportError {
+    fn from(e: SkeletonError) -> Self {
+        ImportError::Skeleton(e)
+    }
+}
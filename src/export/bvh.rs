@@ -0,0 +1,533 @@
+//! Export a skeleton and frames into [BVH](https://en.wikipedia.org/wiki/Biovision_Hierarchy),
+//! and import a skeleton from it.
+
+use crate::float::{atan2, sqrt};
+use crate::{
+    Bone, BoneId, BoneName, ExportError, FramePacket, ImportError, Position, Rotation, Skeleton,
+    SkeletonError, SkeletonPacket, Transform, ROOT_PARENT,
+};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+/// The frame time used when there are not enough frames to compute it, in seconds.
+const DEFAULT_FRAME_TIME: f32 = 1.0 / 60.0;
+
+/// The deepest nesting of joints [`skeleton_from_bvh`] reads, far deeper than the bones of
+/// mocopi.
+const MAX_DEPTH: usize = 64;
+
+/// Write the skeleton and the frames as BVH.
+///
+/// The HIERARCHY is built from the parents of the bones in the skeleton, with the positions
+/// of the bones as the offsets. Each line of the MOTION has the position of the root and the
+/// rotations of all the bones in the `Zrotation Xrotation Yrotation` order. A bone which is
+/// missing in a frame keeps the rotation of the skeleton.
+pub fn to_bvh(skeleton: &SkeletonPacket, frames: &[FramePacket]) -> Result<String, ExportError> {
+    let bones = &skeleton.skeleton.bones;
+    let order = hierarchy_order(bones)?;
+
+    let mut out = String::new();
+    write_bvh(&mut out, bones, &order, frames).expect("writing to a String never fails");
+    Ok(out)
+}
+
+/// A bone in the order it is written, with its depth in the hierarchy.
+struct Node {
+    index: usize,
+    depth: usize,
+}
+
+/// Sort the bones so that every bone follows its parent, like in the HIERARCHY.
+fn hierarchy_order(bones: &[Bone]) -> Result<Vec<Node>, ExportError> {
+    let mut children: BTreeMap<BoneId, Vec<usize>> = BTreeMap::new();
+    let mut roots = Vec::new();
+    let mut dangling = Vec::new();
+    for (i, bone) in bones.iter().enumerate() {
+        if bone.is_root() {
+            roots.push(i);
+        } else if bones.iter().any(|b| b.id == bone.parent) {
+            children.entry(bone.parent).or_default().push(i);
+        } else {
+            dangling.push(bone.id);
+        }
+    }
+    if !dangling.is_empty() {
+        return Err(SkeletonError::DanglingParents(dangling).into());
+    }
+    if roots.is_empty() {
+        return Err(ExportError::NoRoot);
+    }
+
+    let mut order = Vec::new();
+    let mut stack: Vec<Node> = roots
+        .iter()
+        .rev()
+        .map(|&index| Node { index, depth: 0 })
+        .collect();
+    while let Some(node) = stack.pop() {
+        if let Some(c) = children.get(&bones[node.index].id) {
+            stack.extend(c.iter().rev().map(|&index| Node {
+                index,
+                depth: node.depth + 1,
+            }));
+        }
+        order.push(node);
+    }
+
+    // rootからたどれないboneは循環している
+    if order.len() < bones.len() {
+        let ids = bones
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !order.iter().any(|node| node.index == *i))
+            .map(|(_, bone)| bone.id)
+            .collect();
+        return Err(SkeletonError::Cycle(ids).into());
+    }
+
+    Ok(order)
+}
+
+fn write_bvh(
+    out: &mut String,
+    bones: &[Bone],
+    order: &[Node],
+    frames: &[FramePacket],
+) -> fmt::Result {
+    writeln!(out, "HIERARCHY")?;
+    for (i, node) in order.iter().enumerate() {
+        let bone = &bones[node.index];
+        let indent = "\t".repeat(node.depth);
+        let pos = &bone.trans.pos;
+
+        if node.depth == 0 {
+            writeln!(out, "ROOT {}", bone_name(bone.id))?;
+        } else {
+            writeln!(out, "{}JOINT {}", indent, bone_name(bone.id))?;
+        }
+        writeln!(out, "{}{{", indent)?;
+        writeln!(
+            out,
+            "{}\tOFFSET {:.6} {:.6} {:.6}",
+            indent,
+            num(pos.x),
+            num(pos.y),
+            num(pos.z)
+        )?;
+        if node.depth == 0 {
+            writeln!(
+                out,
+                "{}\tCHANNELS 6 Xposition Yposition Zposition Zrotation Xrotation Yrotation",
+                indent
+            )?;
+        } else {
+            writeln!(out, "{}\tCHANNELS 3 Zrotation Xrotation Yrotation", indent)?;
+        }
+
+        // 次のboneの深さまで括弧を閉じる
+        let next_depth = order.get(i + 1).map_or(0, |next| next.depth);
+        if next_depth <= node.depth {
+            writeln!(out, "{}\tEnd Site", indent)?;
+            writeln!(out, "{}\t{{", indent)?;
+            writeln!(out, "{}\t\tOFFSET 0.000000 0.000000 0.000000", indent)?;
+            writeln!(out, "{}\t}}", indent)?;
+            for depth in (next_depth..=node.depth).rev() {
+                writeln!(out, "{}}}", "\t".repeat(depth))?;
+            }
+        }
+    }
+
+    writeln!(out, "MOTION")?;
+    writeln!(out, "Frames: {}", frames.len())?;
+    writeln!(out, "Frame Time: {:.6}", frame_time(frames))?;
+    for packet in frames {
+        let mut values: Vec<f32> = Vec::new();
+        for node in order {
+            let bone = &bones[node.index];
+            let trans: &Transform = packet
+                .frame
+                .bones
+                .iter()
+                .find(|b| b.id == bone.id)
+                .map_or(&bone.trans, |b| &b.trans);
+
+            if node.depth == 0 {
+                values.extend([trans.pos.x, trans.pos.y, trans.pos.z]);
+            }
+            let (z, x, y) = euler_zxy(&trans.rot);
+            values.extend([z.to_degrees(), x.to_degrees(), y.to_degrees()]);
+        }
+
+        let line: Vec<String> = values.iter().map(|v| format!("{:.6}", num(*v))).collect();
+        writeln!(out, "{}", line.join(" "))?;
+    }
+
+    Ok(())
+}
+
+fn bone_name(id: BoneId) -> String {
+    match BoneName::try_from(id) {
+        Ok(name) => String::from(name.name()),
+        Err(_) => format!("bone_{}", id),
+    }
+}
+
+/// The average interval of the frames in seconds.
+fn frame_time(frames: &[FramePacket]) -> f32 {
+    match (frames.first(), frames.last()) {
+        (Some(first), Some(last)) if frames.len() > 1 && last.frame.time > first.frame.time => {
+            (last.frame.time - first.frame.time) as f32 / 1000.0 / (frames.len() - 1) as f32
+        }
+        _ => DEFAULT_FRAME_TIME,
+    }
+}
+
+/// Convert the quaternion to Euler angles `(z, x, y)` in radians, applied in that order.
+fn euler_zxy(r: &Rotation) -> (f32, f32, f32) {
+    let Rotation { x, y, z, w } = *r;
+
+    // 回転行列の要素から角度を求める
+    let m01 = 2.0 * (x * y - w * z);
+    let m11 = 1.0 - 2.0 * (x * x + z * z);
+    let m20 = 2.0 * (x * z - w * y);
+    let m21 = 2.0 * (y * z + w * x);
+    let m22 = 1.0 - 2.0 * (x * x + y * y);
+
+    // asinは±90°付近で精度が落ちるのでatan2を使う
+    let x_angle = atan2(m21, sqrt(m01 * m01 + m11 * m11));
+    let z_angle = atan2(-m01, m11);
+    let y_angle = atan2(-m20, m22);
+
+    (z_angle, x_angle, y_angle)
+}
+
+/// Avoid writing `-0.000000`.
+fn num(v: f32) -> f32 {
+    if v == 0.0 {
+        0.0
+    } else {
+        v
+    }
+}
+
+/// Read the skeleton from the HIERARCHY of BVH, e.g. one written by [`to_bvh`].
+///
+/// The bones are in the order they appear, with their `OFFSET` as the position and no
+/// rotation. Their IDs are those of the mocopi bones with the same names, or `<id>` of the
+/// names `bone_<id>`. The MOTION is ignored.
+///
+/// Returns [`ImportError::TooDeep`] if the joints are nested more than 64 deep.
+pub fn skeleton_from_bvh(bvh: &str) -> Result<Skeleton, ImportError> {
+    let mut tokens = Tokens {
+        tokens: bvh
+            .lines()
+            .enumerate()
+            .flat_map(|(i, line)| line.split_whitespace().map(move |token| (i + 1, token)))
+            .collect(),
+        pos: 0,
+    };
+
+    tokens.expect("HIERARCHY")?;
+    let mut bones = Vec::new();
+    while tokens.peek() == Some("ROOT") {
+        tokens.next()?;
+        read_joint(&mut tokens, ROOT_PARENT, 0, &mut bones)?;
+    }
+    // ROOTの後ろにはMOTIONが続くか、何もない
+    match tokens.next_with_line() {
+        Err(_) | Ok((_, "MOTION")) => {}
+        Ok((line, found)) => return Err(unexpected(line, found)),
+    }
+    if bones.is_empty() {
+        return Err(ImportError::UnexpectedEnd);
+    }
+
+    let skeleton = Skeleton { bones };
+    skeleton.validate()?;
+    Ok(skeleton)
+}
+
+/// Read a ROOT or a JOINT after its keyword, and its children. `depth` is 0 for a ROOT.
+fn read_joint(
+    tokens: &mut Tokens,
+    parent: BoneId,
+    depth: usize,
+    bones: &mut Vec<Bone>,
+) -> Result<(), ImportError> {
+    // 壊れたdataで再帰が深くなりすぎないようにする
+    if depth > MAX_DEPTH {
+        return Err(ImportError::TooDeep { max: MAX_DEPTH });
+    }
+    let name = tokens.next()?;
+    let id = bone_id(name)?;
+    tokens.expect("{")?;
+    tokens.expect("OFFSET")?;
+    let pos = tokens.position()?;
+    bones.push(Bone {
+        id,
+        parent,
+        trans: Transform {
+            rot: Rotation::identity(),
+            pos,
+        },
+    });
+
+    if tokens.peek() == Some("CHANNELS") {
+        tokens.next()?;
+        let (line, count) = tokens.next_with_line()?;
+        let count: usize = count.parse().map_err(|_| unexpected(line, count))?;
+        for _ in 0..count {
+            tokens.next()?;
+        }
+    }
+
+    loop {
+        match tokens.next_with_line()? {
+            (_, "JOINT") => read_joint(tokens, id, depth + 1, bones)?,
+            (_, "End") => {
+                // End Siteはboneにしない
+                tokens.expect("Site")?;
+                tokens.expect("{")?;
+                tokens.expect("OFFSET")?;
+                tokens.position()?;
+                tokens.expect("}")?;
+            }
+            (_, "}") => return Ok(()),
+            (line, found) => return Err(unexpected(line, found)),
+        }
+    }
+}
+
+fn bone_id(name: &str) -> Result<BoneId, ImportError> {
+    if let Some(bone) = BoneName::ALL.iter().find(|bone| bone.name() == name) {
+        return Ok(bone.id());
+    }
+    name.strip_prefix("bone_")
+        .and_then(|id| id.parse().ok())
+        .ok_or_else(|| ImportError::UnknownBone(String::from(name)))
+}
+
+fn unexpected(line: usize, found: &str) -> ImportError {
+    ImportError::UnexpectedToken {
+        line,
+        found: String::from(found),
+    }
+}
+
+/// The tokens of BVH with their line numbers.
+struct Tokens<'a> {
+    tokens: Vec<(usize, &'a str)>,
+    pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).map(|&(_, token)| token)
+    }
+
+    fn next_with_line(&mut self) -> Result<(usize, &'a str), ImportError> {
+        let token = *self
+            .tokens
+            .get(self.pos)
+            .ok_or(ImportError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn next(&mut self) -> Result<&'a str, ImportError> {
+        self.next_with_line().map(|(_, token)| token)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), ImportError> {
+        match self.next_with_line()? {
+            (_, token) if token == expected => Ok(()),
+            (line, found) => Err(unexpected(line, found)),
+        }
+    }
+
+    fn number(&mut self) -> Result<f32, ImportError> {
+        let (line, token) = self.next_with_line()?;
+        token.parse().map_err(|_| unexpected(line, token))
+    }
+
+    fn position(&mut self) -> Result<Position, ImportError> {
+        Ok(Position {
+            x: self.number()?,
+            y: self.number()?,
+            z: self.number()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{head, info};
+    use crate::{BoneTrans, Frame, Position, Skeleton, ROOT_PARENT};
+    use alloc::string::ToString;
+    use alloc::vec;
+    use core::f32::consts::FRAC_1_SQRT_2;
+
+    fn trans(rot: Rotation, pos: Position) -> Transform {
+        Transform { rot, pos }
+    }
+
+    const IDENTITY: Rotation = Rotation::identity();
+
+    fn skeleton() -> SkeletonPacket {
+        let bone = |id, parent, y| Bone {
+            id,
+            parent,
+            trans: trans(IDENTITY, Position { x: 0.0, y, z: 0.0 }),
+        };
+        SkeletonPacket {
+            head: head(),
+            info: info(),
+            skeleton: Skeleton {
+                bones: vec![
+                    bone(0, ROOT_PARENT, 1.0),
+                    bone(1, 0, 0.1),
+                    bone(2, 1, 0.2),
+                    bone(19, 0, -0.1),
+                ],
+            },
+            extra: Vec::new(),
+        }
+    }
+
+    fn frame(num: u32, time: u32, rot: Rotation) -> FramePacket {
+        FramePacket {
+            head: head(),
+            info: info(),
+            frame: Frame {
+                num,
+                time,
+                bones: vec![
+                    BoneTrans {
+                        id: 0,
+                        trans: trans(
+                            IDENTITY,
+                            Position {
+                                x: 0.5,
+                                y: 1.0,
+                                z: -0.5,
+                            },
+                        ),
+                    },
+                    BoneTrans {
+                        id: 1,
+                        trans: trans(
+                            rot,
+                            Position {
+                                x: 0.0,
+                                y: 0.1,
+                                z: 0.0,
+                            },
+                        ),
+                    },
+                ],
+            },
+            extra: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_to_bvh() {
+        let x90 = Rotation {
+            x: FRAC_1_SQRT_2,
+            y: 0.0,
+            z: 0.0,
+            w: FRAC_1_SQRT_2,
+        };
+        let frames = [frame(1, 1000, IDENTITY), frame(2, 1020, x90)];
+
+        let bvh = to_bvh(&skeleton(), &frames).unwrap();
+
+        assert_eq!(bvh, include_str!("testdata/simple.bvh"));
+    }
+
+    #[test]
+    fn test_skeleton_from_bvh() {
+        let bvh = include_str!("testdata/simple.bvh");
+
+        let skeleton = skeleton_from_bvh(bvh).unwrap();
+
+        assert_eq!(skeleton, self::skeleton().skeleton);
+        let ids: Vec<BoneId> = skeleton.bones.iter().map(|bone| bone.id).collect();
+        assert_eq!(ids, [0, 1, 2, 19]);
+        assert_eq!(skeleton.bones[3].parent, 0);
+
+        // 書き出したものを読み戻せる
+        let packet = SkeletonPacket {
+            skeleton,
+            ..self::skeleton()
+        };
+        assert_eq!(
+            skeleton_from_bvh(&to_bvh(&packet, &[]).unwrap()),
+            Ok(packet.skeleton)
+        );
+    }
+
+    #[test]
+    fn test_skeleton_from_bvh_error() {
+        let bvh = include_str!("testdata/simple.bvh");
+
+        assert_eq!(
+            skeleton_from_bvh(&bvh.replace("torso_2", "tail")),
+            Err(ImportError::UnknownBone("tail".to_string()))
+        );
+        assert_eq!(
+            skeleton_from_bvh(&bvh.replace("-0.100000", "abc")),
+            Err(ImportError::UnexpectedToken {
+                line: 22,
+                found: "abc".to_string(),
+            })
+        );
+        assert_eq!(
+            skeleton_from_bvh(&bvh[..bvh.find("MOTION").unwrap() - 3]),
+            Err(ImportError::UnexpectedEnd)
+        );
+        assert_eq!(
+            skeleton_from_bvh(&bvh.replace("torso_2", "bone_19")),
+            Err(ImportError::Skeleton(SkeletonError::DuplicateIds(vec![19])))
+        );
+
+        // 深すぎる入れ子は読まない
+        let mut deep = String::from("HIERARCHY\nROOT bone_0\n{\nOFFSET 0 0 0\n");
+        for id in 1..100_000 {
+            deep += &format!("JOINT bone_{}\n{{\nOFFSET 0 0 0\n", id);
+        }
+        assert_eq!(
+            skeleton_from_bvh(&deep),
+            Err(ImportError::TooDeep { max: MAX_DEPTH })
+        );
+    }
+
+    #[test]
+    fn test_euler_zxy() {
+        let z90 = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: FRAC_1_SQRT_2,
+            w: FRAC_1_SQRT_2,
+        };
+        let (z, x, y) = euler_zxy(&z90);
+
+        assert!((z.to_degrees() - 90.0).abs() < 1e-4);
+        assert!(x.abs() < 1e-6);
+        assert!(y.abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_to_bvh_cycle() {
+        let mut skeleton = skeleton();
+        skeleton.skeleton.bones[1].parent = 2;
+
+        let err = to_bvh(&skeleton, &[]).unwrap_err();
+
+        assert_eq!(err, ExportError::Skeleton(SkeletonError::Cycle(vec![1, 2])));
+    }
+}
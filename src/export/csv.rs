@@ -0,0 +1,70 @@
+//! Export frames into CSV, enabled by the `std` feature.
+
+use crate::FramePacket;
+use std::io::{self, Write};
+
+/// The header row written by [`frames_to_csv`].
+const HEADER: &str = "num,time,bone,rot_x,rot_y,rot_z,rot_w,pos_x,pos_y,pos_z";
+
+/// Write the frames as CSV, one row per bone of each frame.
+///
+/// A header row is written first. The columns follow it, i.e. the frame number, the time,
+/// the bone ID, the rotation in the `x y z w` order and the position.
+pub fn frames_to_csv<W: Write>(frames: &[FramePacket], w: &mut W) -> io::Result<()> {
+    writeln!(w, "{}", HEADER)?;
+    for packet in frames {
+        let frame = &packet.frame;
+        for bone in &frame.bones {
+            let rot = &bone.trans.rot;
+            let pos = &bone.trans.pos;
+            writeln!(
+                w,
+                "{},{},{},{},{},{},{},{},{},{}",
+                frame.num, frame.time, bone.id, rot.x, rot.y, rot.z, rot.w, pos.x, pos.y, pos.z
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{head, info};
+    use crate::{BoneTrans, Frame, Position, Rotation, Transform};
+
+    fn frame(num: u32, time: u32, bones: Vec<BoneTrans>) -> FramePacket {
+        FramePacket {
+            head: head(),
+            info: info(),
+            frame: Frame { num, time, bones },
+            extra: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_frames_to_csv() {
+        let bone = |id, x| BoneTrans {
+            id,
+            trans: Transform {
+                rot: Rotation::identity(),
+                pos: Position { x, y: 1.5, z: 0.0 },
+            },
+        };
+        let frames = [
+            frame(1, 1000, vec![bone(0, 0.5), bone(1, -0.25)]),
+            frame(2, 1020, vec![bone(0, 0.75)]),
+        ];
+
+        let mut out = Vec::new();
+        frames_to_csv(&frames, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "num,time,bone,rot_x,rot_y,rot_z,rot_w,pos_x,pos_y,pos_z\n\
+             1,1000,0,0,0,0,1,0.5,1.5,0\n\
+             1,1000,1,0,0,0,1,-0.25,1.5,0\n\
+             2,1020,0,0,0,0,1,0.75,1.5,0\n"
+        );
+    }
+}
@@ -0,0 +1,5 @@
+//! Export parsed packets into other formats.
+
+pub mod bvh;
+#[cfg(feature = "std")]
+pub mod csv;
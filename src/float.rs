@@ -0,0 +1,51 @@
+//! Float functions which are not available in `core`, backed by `libm` without `std`.
+
+#[cfg(feature = "std")]
+pub(crate) fn sqrt(x: f32) -> f32 {
+    x.sqrt()
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn sqrt(x: f32) -> f32 {
+    libm::sqrtf(x)
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn asin(x: f32) -> f32 {
+    x.asin()
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn asin(x: f32) -> f32 {
+    libm::asinf(x)
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn atan2(y: f32, x: f32) -> f32 {
+    y.atan2(x)
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn atan2(y: f32, x: f32) -> f32 {
+    libm::atan2f(y, x)
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn acos(x: f32) -> f32 {
+    x.acos()
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn acos(x: f32) -> f32 {
+    libm::acosf(x)
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn sin(x: f32) -> f32 {
+    x.sin()
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn sin(x: f32) -> f32 {
+    libm::sinf(x)
+}
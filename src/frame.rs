@@ -0,0 +1,647 @@
+use crate::float::acos;
+use crate::{BoneId, BoneTrans, Frame, Position, Rotation, Skeleton, Transform};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::slice;
+use core::time::Duration;
+
+impl Frame {
+    /// The frame number, counted up by mocopi for each frame.
+    pub fn frame_number(&self) -> u32 {
+        self.num
+    }
+
+    /// Compare the frame numbers, treating `num` as wrapping around after `u32::MAX`.
+    ///
+    /// A frame is later than another if it is less than half of the range ahead of it, so
+    /// `0` is later than `u32::MAX`. This only orders frames which are close to each other,
+    /// e.g. in a jitter buffer.
+    pub fn cmp_by_num(&self, other: &Frame) -> Ordering {
+        (self.num.wrapping_sub(other.num) as i32).cmp(&0)
+    }
+
+    /// The time since mocopi started sending, read from `time` in milliseconds.
+    pub fn duration_since_start(&self) -> Duration {
+        Duration::from_millis(self.time as u64)
+    }
+
+    /// Find the transform of the bone with the given ID.
+    pub fn bone_by_id(&self, id: BoneId) -> Option<&BoneTrans> {
+        self.bones.iter().find(|bone| bone.id == id)
+    }
+
+    /// The position of the root bone of `skeleton`, which carries the movement of the whole
+    /// body while the other bones are relative to their parents.
+    ///
+    /// Returns `None` if the skeleton has no root or the frame does not have it. If there are
+    /// several roots, the first one in the skeleton is used.
+    pub fn root_position(&self, skeleton: &Skeleton) -> Option<Position> {
+        let root = skeleton.bones.iter().find(|bone| bone.is_root())?;
+        self.bone_by_id(root.id).map(|bone| bone.trans.pos)
+    }
+
+    /// Zero the horizontal position of the root bone of `skeleton`, keeping its height, so
+    /// that the body moves in place.
+    ///
+    /// mocopi is Y-up, so X and Z are zeroed. The root is found like in
+    /// [`root_position`](Frame::root_position), and nothing changes if it is not found.
+    pub fn lock_root_xz(&mut self, skeleton: &Skeleton) {
+        if let Some(root) = self.root_mut(skeleton) {
+            root.trans.pos.x = 0.0;
+            root.trans.pos.z = 0.0;
+        }
+    }
+
+    /// Zero the position of the root bone of `skeleton`.
+    ///
+    /// The root is found like in [`root_position`](Frame::root_position), and nothing changes
+    /// if it is not found.
+    pub fn zero_root_translation(&mut self, skeleton: &Skeleton) {
+        if let Some(root) = self.root_mut(skeleton) {
+            root.trans.pos = Position::zero();
+        }
+    }
+
+    fn root_mut(&mut self, skeleton: &Skeleton) -> Option<&mut BoneTrans> {
+        let root = skeleton.bones.iter().find(|bone| bone.is_root())?;
+        self.bones.iter_mut().find(|bone| bone.id == root.id)
+    }
+
+    /// Sort the bones by ID, since mocopi does not guarantee their order.
+    pub fn sort_by_id(&mut self) {
+        self.bones.sort_by_key(|bone| bone.id);
+    }
+
+    /// Summarize the bones for checking the health of a stream.
+    pub fn stats(&self) -> FrameStats {
+        let bounds: Option<(Position, Position)> = self.bones.iter().fold(None, |bounds, bone| {
+            let p = bone.trans.pos;
+            Some(match bounds {
+                None => (p, p),
+                Some((min, max)) => (
+                    Position {
+                        x: p.x.min(min.x),
+                        y: p.y.min(min.y),
+                        z: p.z.min(min.z),
+                    },
+                    Position {
+                        x: p.x.max(max.x),
+                        y: p.y.max(max.y),
+                        z: p.z.max(max.z),
+                    },
+                ),
+            })
+        });
+
+        FrameStats {
+            bone_count: self.bones.len(),
+            bounds,
+            non_unit_rotation: self
+                .bones
+                .iter()
+                .any(|bone| !is_unit(&bone.trans.rot, UNIT_TOLERANCE)),
+        }
+    }
+
+    /// The average of the positions of the bones, or `None` without bones.
+    pub fn centroid(&self) -> Option<Position> {
+        if self.bones.is_empty() {
+            return None;
+        }
+
+        let sum = self
+            .bones
+            .iter()
+            .fold(Position::zero(), |sum, bone| sum + bone.trans.pos);
+        Some(sum * (1.0 / self.bones.len() as f32))
+    }
+
+    /// A sphere around the [`centroid`](Frame::centroid) which encloses the positions of all
+    /// bones, as `(center, radius)`, or `None` without bones.
+    ///
+    /// The radius is the smallest one for that center, i.e. the distance to the farthest
+    /// bone. The sphere is not always the smallest one enclosing the bones.
+    pub fn bounding_sphere(&self) -> Option<(Position, f32)> {
+        let center = self.centroid()?;
+        let radius = self
+            .bones
+            .iter()
+            .map(|bone| bone.trans.pos.distance(&center))
+            .fold(0.0, f32::max);
+        Some((center, radius))
+    }
+
+    /// Whether the frame looks intact: the length of every rotation is within `tol` of 1 and
+    /// every position is finite.
+    pub fn looks_valid(&self, tol: f32) -> bool {
+        self.bones.iter().all(|bone| {
+            let p = &bone.trans.pos;
+            is_unit(&bone.trans.rot, tol) && p.x.is_finite() && p.y.is_finite() && p.z.is_finite()
+        })
+    }
+}
+
+/// Iterate over the bones in the order of `bones`.
+impl<'a> IntoIterator for &'a Frame {
+    type Item = &'a BoneTrans;
+    type IntoIter = slice::Iter<'a, BoneTrans>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.bones.iter()
+    }
+}
+
+fn is_unit(rot: &Rotation, tol: f32) -> bool {
+    // NaNのときもfalseになる
+    (rot.magnitude() - 1.0).abs() <= tol
+}
+
+/// How far the length of a rotation may be from 1 before [`FrameStats`] reports it.
+pub const UNIT_TOLERANCE: f32 = 1e-3;
+
+/// A summary of a frame, returned by [`Frame::stats`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameStats {
+    pub bone_count: usize,
+    /// The minimum and maximum of the positions on each axis, or `None` without bones.
+    pub bounds: Option<(Position, Position)>,
+    /// Whether the length of any rotation is off by more than [`UNIT_TOLERANCE`], which is a
+    /// sign of corrupted data.
+    pub non_unit_rotation: bool,
+}
+
+/// Interpolate between two frames, from `a` (`t = 0`) to `b` (`t = 1`).
+///
+/// Bones are matched by ID. Positions are interpolated linearly and rotations with
+/// [`Rotation::slerp`](crate::Rotation::slerp). Bones which are only in one of the frames
+/// are passed through unchanged.
+pub fn lerp_frame(a: &Frame, b: &Frame, t: f32) -> Frame {
+    let mut bones: Vec<BoneTrans> = a
+        .bones
+        .iter()
+        .map(
+            |bone| match b.bones.iter().find(|other| other.id == bone.id) {
+                Some(other) => BoneTrans {
+                    id: bone.id,
+                    trans: lerp_trans(&bone.trans, &other.trans, t),
+                },
+                None => bone.clone(),
+            },
+        )
+        .collect();
+    bones.extend(
+        b.bones
+            .iter()
+            .filter(|bone| !a.bones.iter().any(|other| other.id == bone.id))
+            .cloned(),
+    );
+
+    let time = a.time as f64 + (b.time as f64 - a.time as f64) * t as f64;
+    Frame {
+        num: if t < 0.5 { a.num } else { b.num },
+        // 四捨五入する
+        time: (time + 0.5) as u32,
+        bones,
+    }
+}
+
+/// The change of a bone between two frames, returned by [`frame_delta`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoneDelta {
+    pub id: BoneId,
+    /// The change of the position, `cur - prev`.
+    pub linear: Position,
+    /// The rotation from the previous to the current one, `cur * prev.conjugate()`.
+    pub angular: Rotation,
+}
+
+/// Compute the change of each bone from `prev` to `cur`.
+///
+/// Bones are matched by ID, and the deltas are returned in the order of `cur`. Bones which
+/// are only in one of the frames are skipped.
+pub fn frame_delta(prev: &Frame, cur: &Frame) -> Vec<BoneDelta> {
+    cur.bones
+        .iter()
+        .filter_map(|bone| {
+            let before = prev.bones.iter().find(|other| other.id == bone.id)?;
+            Some(BoneDelta {
+                id: bone.id,
+                linear: bone.trans.pos - before.trans.pos,
+                angular: bone.trans.rot * before.trans.rot.conjugate(),
+            })
+        })
+        .collect()
+}
+
+/// Whether no bone moved between `a` and `b`: every position moved less than `pos_eps` and
+/// every rotation turned less than `rot_eps` radians.
+///
+/// Bones are matched by ID. A bone which is only in one of the frames counts as moved.
+pub fn frames_are_still(a: &Frame, b: &Frame, pos_eps: f32, rot_eps: f32) -> bool {
+    a.bones.len() == b.bones.len()
+        && a.bones.iter().all(|bone| {
+            let Some(other) = b.bones.iter().find(|other| other.id == bone.id) else {
+                return false;
+            };
+            // qと-qは同じ回転なので絶対値をとる
+            let dot = bone.trans.rot.dot(&other.trans.rot).abs().min(1.0);
+            bone.trans.pos.distance(&other.trans.pos) < pos_eps && 2.0 * acos(dot) < rot_eps
+        })
+}
+
+pub(crate) fn lerp_trans(a: &Transform, b: &Transform, t: f32) -> Transform {
+    Transform {
+        rot: a.rot.slerp(&b.rot, t),
+        pos: Position {
+            x: a.pos.x + (b.pos.x - a.pos.x) * t,
+            y: a.pos.y + (b.pos.y - a.pos.y) * t,
+            z: a.pos.z + (b.pos.z - a.pos.z) * t,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn bone(id: u16, x: f32, rot: Rotation) -> BoneTrans {
+        BoneTrans {
+            id,
+            trans: Transform {
+                rot,
+                pos: Position { x, y: 0.0, z: 0.0 },
+            },
+        }
+    }
+
+    #[test]
+    fn test_duration_since_start() {
+        let frame = Frame {
+            num: 90,
+            time: 1500,
+            bones: Vec::new(),
+        };
+
+        assert_eq!(frame.frame_number(), 90);
+        assert_eq!(frame.duration_since_start(), Duration::from_millis(1500));
+        assert_eq!(frame.duration_since_start().as_secs_f32(), 1.5);
+    }
+
+    #[test]
+    fn test_lock_root() {
+        let skel_bone = |id, parent| crate::Bone {
+            id,
+            parent,
+            trans: Transform::identity(),
+        };
+        let skeleton = Skeleton {
+            bones: vec![skel_bone(1, 0), skel_bone(0, crate::ROOT_PARENT)],
+        };
+        let p = Position {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+        };
+        let mut frame = Frame {
+            num: 1,
+            time: 0,
+            bones: vec![
+                bone(0, 0.0, Rotation::identity()),
+                bone(1, 0.0, Rotation::identity()),
+            ],
+        };
+        frame.bones[0].trans.pos = p;
+        frame.bones[1].trans.pos = p;
+
+        let mut locked = frame.clone();
+        locked.lock_root_xz(&skeleton);
+        assert_eq!(
+            locked.bones[0].trans.pos,
+            Position {
+                x: 0.0,
+                y: 2.0,
+                z: 0.0
+            }
+        );
+        assert_eq!(locked.bones[0].trans.rot, frame.bones[0].trans.rot);
+        assert_eq!(locked.bones[1], frame.bones[1]);
+
+        let mut zeroed = frame.clone();
+        zeroed.zero_root_translation(&skeleton);
+        assert_eq!(zeroed.bones[0].trans.pos, Position::zero());
+        assert_eq!(zeroed.bones[1], frame.bones[1]);
+
+        // rootがないときは何もしない
+        let mut unchanged = frame.clone();
+        unchanged.lock_root_xz(&Skeleton { bones: Vec::new() });
+        assert_eq!(unchanged, frame);
+    }
+
+    #[test]
+    fn test_cmp_by_num() {
+        let frame = |num| Frame {
+            num,
+            time: 0,
+            bones: Vec::new(),
+        };
+
+        assert_eq!(frame(1).cmp_by_num(&frame(2)), Ordering::Less);
+        assert_eq!(frame(2).cmp_by_num(&frame(2)), Ordering::Equal);
+        assert_eq!(frame(3).cmp_by_num(&frame(2)), Ordering::Greater);
+
+        // u32::MAXの次は0に戻る
+        assert_eq!(frame(0).cmp_by_num(&frame(u32::MAX)), Ordering::Greater);
+        assert_eq!(frame(u32::MAX - 1).cmp_by_num(&frame(1)), Ordering::Less);
+
+        let mut frames = [frame(1), frame(u32::MAX), frame(0), frame(u32::MAX - 1)];
+        frames.sort_by(Frame::cmp_by_num);
+        let nums: Vec<u32> = frames.iter().map(|frame| frame.num).collect();
+        assert_eq!(nums, [u32::MAX - 1, u32::MAX, 0, 1]);
+    }
+
+    #[test]
+    fn test_root_position() {
+        let skel_bone = |id, parent| crate::Bone {
+            id,
+            parent,
+            trans: Transform::identity(),
+        };
+        let skeleton = Skeleton {
+            bones: vec![
+                skel_bone(1, 0),
+                skel_bone(0, crate::ROOT_PARENT),
+                skel_bone(2, 1),
+            ],
+        };
+        let mut frame = Frame {
+            num: 1,
+            time: 0,
+            bones: vec![
+                bone(2, 0.2, Rotation::identity()),
+                bone(1, 0.1, Rotation::identity()),
+                bone(0, 5.0, Rotation::identity()),
+            ],
+        };
+
+        assert_eq!(
+            frame.root_position(&skeleton),
+            Some(Position {
+                x: 5.0,
+                y: 0.0,
+                z: 0.0
+            })
+        );
+
+        frame.bones.pop();
+        assert_eq!(frame.root_position(&skeleton), None);
+        assert_eq!(frame.root_position(&Skeleton { bones: Vec::new() }), None);
+    }
+
+    #[test]
+    fn test_lerp_frame() {
+        let identity = Rotation::identity();
+        let z90 = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: core::f32::consts::FRAC_1_SQRT_2,
+            w: core::f32::consts::FRAC_1_SQRT_2,
+        };
+        let a = Frame {
+            num: 1,
+            time: 100,
+            bones: vec![
+                bone(0, 0.0, identity),
+                bone(1, 1.0, identity),
+                bone(2, 5.0, z90),
+            ],
+        };
+        let b = Frame {
+            num: 2,
+            time: 200,
+            bones: vec![
+                bone(3, 7.0, identity),
+                bone(1, 3.0, z90),
+                bone(0, 2.0, identity),
+            ],
+        };
+
+        let frame = lerp_frame(&a, &b, 0.5);
+
+        assert_eq!(frame.num, 2);
+        assert_eq!(frame.time, 150);
+        let ids: Vec<u16> = frame.bones.iter().map(|bone| bone.id).collect();
+        assert_eq!(ids, [0, 1, 2, 3]);
+
+        assert_eq!(frame.bones[0].trans.pos.x, 1.0);
+        assert_eq!(frame.bones[1].trans.pos.x, 2.0);
+        assert!((frame.bones[1].trans.rot.z - 0.38268343).abs() < 1e-6);
+
+        // 片方にしかないboneはそのまま
+        assert_eq!(frame.bones[2], a.bones[2]);
+        assert_eq!(frame.bones[3], b.bones[0]);
+    }
+
+    #[test]
+    fn test_frame_delta() {
+        let z90 = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: core::f32::consts::FRAC_1_SQRT_2,
+            w: core::f32::consts::FRAC_1_SQRT_2,
+        };
+        let prev = Frame {
+            num: 1,
+            time: 100,
+            bones: vec![
+                bone(0, 1.0, Rotation::identity()),
+                bone(1, 0.0, z90),
+                bone(2, 0.0, Rotation::identity()),
+            ],
+        };
+        let cur = Frame {
+            num: 2,
+            time: 120,
+            bones: vec![
+                bone(1, 0.5, z90),
+                bone(0, 3.0, z90),
+                bone(3, 0.0, Rotation::identity()),
+            ],
+        };
+
+        let deltas = frame_delta(&prev, &cur);
+
+        assert_eq!(deltas.len(), 2);
+        assert_eq!(deltas[0].id, 1);
+        assert_eq!(deltas[0].linear.x, 0.5);
+        assert!((deltas[0].angular.w - 1.0).abs() < 1e-6);
+        assert!(deltas[0].angular.z.abs() < 1e-6);
+
+        assert_eq!(deltas[1].id, 0);
+        assert_eq!(deltas[1].linear.x, 2.0);
+        assert_eq!(deltas[1].angular, z90);
+    }
+
+    #[test]
+    fn test_frames_are_still() {
+        let a = Frame {
+            num: 1,
+            time: 100,
+            bones: vec![
+                bone(0, 1.0, Rotation::identity()),
+                bone(1, 2.0, Rotation::identity()),
+            ],
+        };
+        assert!(frames_are_still(&a, &a.clone(), 1e-3, 1e-3));
+
+        // 順番が違ってもIDで対応させる
+        let mut b = a.clone();
+        b.bones.reverse();
+        b.bones[0].trans.pos.x += 1e-4;
+        assert!(frames_are_still(&a, &b, 1e-3, 1e-3));
+
+        b.bones[0].trans.pos.x += 1e-2;
+        assert!(!frames_are_still(&a, &b, 1e-3, 1e-3));
+        assert!(frames_are_still(&a, &b, 1e-1, 1e-3));
+
+        let mut b = a.clone();
+        b.bones[1].trans.rot = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: 0.01,
+            w: 1.0,
+        }
+        .normalize();
+        assert!(!frames_are_still(&a, &b, 1e-3, 1e-3));
+        assert!(frames_are_still(&a, &b, 1e-3, 1e-1));
+
+        // 片方にしかないboneは動いたとみなす
+        let mut b = a.clone();
+        b.bones[1].id = 2;
+        assert!(!frames_are_still(&a, &b, 1.0, 1.0));
+        b.bones.pop();
+        assert!(!frames_are_still(&a, &b, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut frame = Frame {
+            num: 1,
+            time: 100,
+            bones: vec![
+                bone(0, 1.0, Rotation::identity()),
+                bone(1, -2.0, Rotation::identity()),
+            ],
+        };
+        frame.bones[1].trans.pos.y = 0.5;
+        frame.bones[1].trans.pos.z = -0.25;
+
+        let stats = frame.stats();
+
+        assert_eq!(stats.bone_count, 2);
+        assert_eq!(
+            stats.bounds,
+            Some((
+                Position {
+                    x: -2.0,
+                    y: 0.0,
+                    z: -0.25
+                },
+                Position {
+                    x: 1.0,
+                    y: 0.5,
+                    z: 0.0
+                },
+            ))
+        );
+        assert!(!stats.non_unit_rotation);
+
+        frame.bones[0].trans.rot.w = 0.5;
+        assert!(frame.stats().non_unit_rotation);
+
+        frame.bones.clear();
+        assert_eq!(frame.stats().bounds, None);
+    }
+
+    #[test]
+    fn test_bounding_sphere() {
+        let mut frame = Frame {
+            num: 1,
+            time: 0,
+            bones: vec![
+                bone(0, -1.0, Rotation::identity()),
+                bone(1, 3.0, Rotation::identity()),
+                bone(2, 1.0, Rotation::identity()),
+            ],
+        };
+        frame.bones[2].trans.pos.y = 3.0;
+
+        let center = Position {
+            x: 1.0,
+            y: 1.0,
+            z: 0.0,
+        };
+        assert_eq!(frame.centroid(), Some(center));
+
+        let (c, radius) = frame.bounding_sphere().unwrap();
+        assert_eq!(c, center);
+        assert!((radius - 5.0f32.sqrt()).abs() < 1e-6);
+        assert!(frame
+            .bones
+            .iter()
+            .all(|bone| bone.trans.pos.distance(&c) <= radius));
+
+        frame.bones.clear();
+        assert_eq!(frame.centroid(), None);
+        assert_eq!(frame.bounding_sphere(), None);
+    }
+
+    #[test]
+    fn test_looks_valid() {
+        let mut frame = Frame {
+            num: 1,
+            time: 100,
+            bones: vec![
+                bone(0, 1.0, Rotation::identity()),
+                bone(1, -2.0, Rotation::identity()),
+            ],
+        };
+        assert!(frame.looks_valid(1e-3));
+
+        frame.bones[1].trans.rot.x = 0.1;
+        assert!(!frame.looks_valid(1e-3));
+        assert!(frame.looks_valid(1e-2));
+
+        frame.bones[1].trans.rot = Rotation::identity();
+        frame.bones[0].trans.pos.y = f32::NAN;
+        assert!(!frame.looks_valid(1e-3));
+
+        frame.bones[0].trans.pos.y = f32::INFINITY;
+        assert!(!frame.looks_valid(1e-3));
+    }
+
+    #[test]
+    fn test_bone_by_id() {
+        let identity = Rotation::identity();
+        let mut frame = Frame {
+            num: 1,
+            time: 0,
+            bones: vec![
+                bone(2, 0.2, identity),
+                bone(0, 0.0, identity),
+                bone(1, 0.1, identity),
+            ],
+        };
+
+        assert_eq!(frame.bone_by_id(1).unwrap().trans.pos.x, 0.1);
+        assert!(frame.bone_by_id(3).is_none());
+
+        frame.sort_by_id();
+        let ids: Vec<BoneId> = frame.bones.iter().map(|b| b.id).collect();
+        assert_eq!(ids, [0, 1, 2]);
+        assert_eq!(frame.bone_by_id(1).unwrap().trans.pos.x, 0.1);
+
+        let xs: Vec<f32> = (&frame).into_iter().map(|b| b.trans.pos.x).collect();
+        assert_eq!(xs, [0.0, 0.1, 0.2]);
+    }
+}
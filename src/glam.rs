@@ -0,0 +1,130 @@
+//! Conversions to and from [`glam`] types, enabled by the `glam` feature.
+
+use crate::{Position, Rotation, Transform};
+use ::glam::{Affine3A, Quat, Vec3};
+
+impl From<Rotation> for Quat {
+    fn from(r: Rotation) -> Self {
+        Quat::from_xyzw(r.x, r.y, r.z, r.w)
+    }
+}
+
+impl From<Quat> for Rotation {
+    fn from(q: Quat) -> Self {
+        Rotation {
+            x: q.x,
+            y: q.y,
+            z: q.z,
+            w: q.w,
+        }
+    }
+}
+
+impl From<Position> for Vec3 {
+    fn from(p: Position) -> Self {
+        Vec3::new(p.x, p.y, p.z)
+    }
+}
+
+impl From<Vec3> for Position {
+    fn from(v: Vec3) -> Self {
+        Position {
+            x: v.x,
+            y: v.y,
+            z: v.z,
+        }
+    }
+}
+
+impl From<Transform> for Affine3A {
+    fn from(t: Transform) -> Self {
+        Affine3A::from_rotation_translation(t.rot.into(), t.pos.into())
+    }
+}
+
+impl From<Affine3A> for Transform {
+    /// Any scale in the affine transform is discarded.
+    fn from(a: Affine3A) -> Self {
+        let (_, rot, pos) = a.to_scale_rotation_translation();
+        Transform {
+            rot: rot.into(),
+            pos: pos.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation() {
+        let r = Rotation {
+            x: 0.1,
+            y: 0.2,
+            z: 0.3,
+            w: 0.4,
+        };
+
+        let q: Quat = r.into();
+        assert_eq!(q.to_array(), [0.1, 0.2, 0.3, 0.4]);
+
+        let r: Rotation = q.into();
+        assert_eq!(
+            r,
+            Rotation {
+                x: 0.1,
+                y: 0.2,
+                z: 0.3,
+                w: 0.4,
+            }
+        );
+    }
+
+    #[test]
+    fn test_position() {
+        let v: Vec3 = Position {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+        }
+        .into();
+        assert_eq!(v.to_array(), [1.0, 2.0, 3.0]);
+
+        let p: Position = v.into();
+        assert_eq!(
+            p,
+            Position {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        let t = Transform {
+            rot: Rotation {
+                x: 0.0,
+                y: 0.0,
+                z: std::f32::consts::FRAC_1_SQRT_2,
+                w: std::f32::consts::FRAC_1_SQRT_2,
+            },
+            pos: Position {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            },
+        };
+
+        let a: Affine3A = t.into();
+        assert_eq!(a.translation.to_array(), [1.0, 2.0, 3.0]);
+        let v = a.transform_point3(Vec3::X);
+        assert!(v.abs_diff_eq(Vec3::new(1.0, 3.0, 3.0), 1e-6));
+
+        let t: Transform = a.into();
+        assert!((t.rot.z - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+        assert_eq!(t.pos.y, 2.0);
+    }
+}
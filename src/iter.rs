@@ -0,0 +1,397 @@
+use crate::{
+    is_extra_section, parse_value_with, parse_with_len, tags, Data, ParseError, ParseOptions,
+    SkeletonOrFrame,
+};
+use alloc::vec::Vec;
+
+/// The length of the packet at the start of `data`, read only from the top-level sections.
+pub(crate) fn packet_len(data: &[u8]) -> Result<usize, ParseError> {
+    match packet_extent(data, true)? {
+        PacketExtent::Complete(len) => Ok(len),
+        PacketExtent::Partial(len) => Err(ParseError::UnexpectedEof {
+            offset: 0,
+            expected: len,
+            found: data.len(),
+        }),
+    }
+}
+
+/// Where a packet ends, returned by [`packet_extent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PacketExtent {
+    /// The packet is the first this many bytes of the data.
+    Complete(usize),
+    /// The packet needs at least this many bytes, which are more than the data has.
+    Partial(usize),
+}
+
+/// Find where the packet at the start of `data` ends from the lengths of its top-level
+/// sections, without parsing their values.
+///
+/// The sections after `skdf` or `fram` end like in [`parse`](crate::parse): at the `head` of
+/// the next packet, or at data which is not a section. When more data may follow, i.e.
+/// `at_end` is false, a section or a name which is cut off after them makes the packet
+/// [`PacketExtent::Partial`]. A packet which ends right at the end of `data` is complete
+/// either way.
+pub(crate) fn packet_extent(data: &[u8], at_end: bool) -> Result<PacketExtent, ParseError> {
+    let mut offset = 0;
+    // head, info, skdfかframの3つのsectionは必ずある
+    for _ in 0..3 {
+        let Some(header) = data.get(offset..offset + 8) else {
+            return Ok(PacketExtent::Partial(offset + 8));
+        };
+        core::str::from_utf8(&header[4..])?;
+        offset = section_end(offset, header);
+        if offset > data.len() {
+            return Ok(PacketExtent::Partial(offset));
+        }
+    }
+
+    loop {
+        let end = match data.get(offset..offset + 8) {
+            Some(header) => match core::str::from_utf8(&header[4..]) {
+                Ok(name) if is_extra_section(name, &ParseOptions::default()) => {
+                    section_end(offset, header)
+                }
+                _ => return Ok(PacketExtent::Complete(offset)),
+            },
+            None if at_end || offset == data.len() => return Ok(PacketExtent::Complete(offset)),
+            // nameが届くまで、続くsectionかどうかわからない
+            None => return Ok(PacketExtent::Partial(offset + 8)),
+        };
+        if end > data.len() {
+            return Ok(if at_end {
+                PacketExtent::Complete(offset)
+            } else {
+                PacketExtent::Partial(end)
+            });
+        }
+        offset = end;
+    }
+}
+
+/// The end of the section which starts at `offset` with `header`.
+fn section_end(offset: usize, header: &[u8]) -> usize {
+    let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    offset.saturating_add(8).saturating_add(len)
+}
+
+/// An iterator over the packets in a buffer, created by [`iter_packets`].
+#[derive(Debug, Clone)]
+pub struct PacketIter<'a> {
+    remain: &'a [u8],
+}
+
+impl<'a> Iterator for PacketIter<'a> {
+    type Item = Result<SkeletonOrFrame, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remain.is_empty() {
+            return None;
+        }
+
+        match parse_with_len(self.remain) {
+            Ok((len, packet)) => {
+                self.remain = &self.remain[len..];
+                Some(Ok(packet))
+            }
+            Err(e) => {
+                // sectionの区切りが読めれば次のpacketから続け、読めなければ終わる
+                self.remain = match packet_len(self.remain) {
+                    Ok(len) => &self.remain[len..],
+                    Err(_) => &[],
+                };
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Iterate over the packets in a buffer which holds them back to back.
+///
+/// # Examples
+///
+/// ```no_run
+/// let buf: Vec<u8> = std::fs::read("capture.bin").unwrap();
+///
+/// for packet in mocopi_parser::iter_packets(&buf) {
+///     match packet {
+///         Ok(packet) => { /* ... */ }
+///         Err(e) => eprintln!("{}", e),
+///     }
+/// }
+/// ```
+pub fn iter_packets(data: &[u8]) -> PacketIter<'_> {
+    PacketIter { remain: data }
+}
+
+/// Parse the packets at the start of a buffer until one fails.
+///
+/// Returns the parsed packets and the number of bytes left after them, e.g. a trailing
+/// partial packet.
+pub fn parse_many(data: &[u8]) -> (Vec<SkeletonOrFrame>, usize) {
+    let mut packets = Vec::new();
+    let mut remain = data;
+    while let Ok((len, packet)) = parse_with_len(remain) {
+        packets.push(packet);
+        remain = &remain[len..];
+    }
+    (packets, remain.len())
+}
+
+/// Parse all the packets in a buffer, skipping over corrupted data.
+///
+/// After a packet fails, the parsing resumes at the next place which looks like the start of a
+/// packet, i.e. a `head` whose length fits in the buffer. Each error comes with the offset in
+/// `data` of the packet which failed.
+pub fn parse_all_lossy(data: &[u8]) -> Vec<Result<SkeletonOrFrame, (usize, ParseError)>> {
+    let mut results = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        match parse_with_len(&data[offset..]) {
+            Ok((len, packet)) => {
+                results.push(Ok(packet));
+                offset += len;
+            }
+            Err(e) => {
+                results.push(Err((offset, e)));
+                match find_packet_start(&data[offset + 1..]) {
+                    Some(pos) => offset += 1 + pos,
+                    None => break,
+                }
+            }
+        }
+    }
+    results
+}
+
+/// Find the first `head` whose length fits in the buffer.
+fn find_packet_start(data: &[u8]) -> Option<usize> {
+    (0..data.len().saturating_sub(7)).find(|&i| {
+        let rem = &data[i..];
+        let len = u32::from_le_bytes([rem[0], rem[1], rem[2], rem[3]]) as usize;
+        &rem[4..8] == tags::HEAD.as_bytes() && len <= rem.len() - 8
+    })
+}
+
+/// An iterator over the values in a buffer, created by [`values`] or [`Data::values`].
+#[derive(Debug, Clone)]
+pub struct ValueIter<'a> {
+    remain: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for ValueIter<'a> {
+    type Item = Result<Data<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remain.is_empty() {
+            return None;
+        }
+
+        match parse_value_with(self.remain, self.offset, &ParseOptions::default()) {
+            Ok(value) => {
+                self.remain = value.rem;
+                self.offset = value.rem_offset();
+                Some(Ok(value))
+            }
+            Err(e) => {
+                // 区切りが読めないので終わる
+                self.remain = &[];
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Iterate over the length, name and data of the values at the top level of a buffer.
+///
+/// The values are not checked against the layout of a packet, so this works for sections
+/// which this crate does not model. The data of a value can be walked with [`Data::values`].
+///
+/// # Examples
+///
+/// ```
+/// # let buf: Vec<u8> = Vec::new();
+/// for value in mocopi_parser::values(&buf) {
+///     let value = value.unwrap();
+///     println!("{} at {}: {} bytes", value.name, value.offset, value.len);
+/// }
+/// ```
+pub fn values(data: &[u8]) -> ValueIter<'_> {
+    ValueIter {
+        remain: data,
+        offset: 0,
+    }
+}
+
+impl<'a> Data<'a> {
+    /// Iterate over the values in the data of this value, like [`values`].
+    ///
+    /// Their offsets are counted from the start of the packet, like the offset of this value.
+    pub fn values(&self) -> ValueIter<'a> {
+        ValueIter {
+            remain: self.data,
+            offset: self.data_offset(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode_frame, test_util};
+
+    fn frame_packet(num: u32) -> Vec<u8> {
+        encode_frame(&test_util::frame_packet(num)).unwrap()
+    }
+
+    fn num(packet: SkeletonOrFrame) -> u32 {
+        match packet {
+            SkeletonOrFrame::Frame(packet) => packet.frame.num,
+            SkeletonOrFrame::Skeleton(_) => panic!("expected a frame packet"),
+        }
+    }
+
+    #[test]
+    fn test_iter_packets() {
+        let raw = [frame_packet(1), frame_packet(2), frame_packet(3)].concat();
+
+        let nums: Vec<u32> = iter_packets(&raw).map(|p| num(p.unwrap())).collect();
+
+        assert_eq!(nums, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_iter_packets_error() {
+        // 2つ目のpacketのtranを壊す
+        let mut broken = frame_packet(2);
+        let len = broken.len();
+        broken[len - 36..len - 32].copy_from_slice(&20u32.to_le_bytes());
+        let raw = [frame_packet(1), broken, frame_packet(3)].concat();
+
+        let packets: Vec<_> = iter_packets(&raw).collect();
+
+        assert_eq!(packets.len(), 3);
+        let mut packets = packets.into_iter();
+        assert_eq!(num(packets.next().unwrap().unwrap()), 1);
+        assert!(packets.next().unwrap().is_err());
+        assert_eq!(num(packets.next().unwrap().unwrap()), 3);
+    }
+
+    #[test]
+    fn test_parse_many() {
+        let partial = frame_packet(3);
+        let raw = [frame_packet(1), frame_packet(2), partial[..20].to_vec()].concat();
+
+        let (packets, leftover) = parse_many(&raw);
+
+        let nums: Vec<u32> = packets.into_iter().map(num).collect();
+        assert_eq!(nums, [1, 2]);
+        assert_eq!(leftover, 20);
+
+        assert_eq!(parse_many(&[]), (Vec::new(), 0));
+    }
+
+    #[test]
+    fn test_packet_extent() {
+        let packet = frame_packet(1);
+        let extra = [0x02, 0x00, 0x00, 0x00, b'x', b't', b'r', b'a', 0x01, 0x02];
+        let with_extra = [packet.clone(), extra.to_vec()].concat();
+        let next = [with_extra.clone(), frame_packet(2)].concat();
+        let len = with_extra.len();
+
+        assert_eq!(packet_extent(&next, false), Ok(PacketExtent::Complete(len)));
+        assert_eq!(
+            packet_extent(&with_extra, false),
+            Ok(PacketExtent::Complete(len))
+        );
+        assert_eq!(packet_len(&next), Ok(len));
+
+        // 後ろのsectionが途中で切れている
+        for cut in [packet.len() + 3, len - 1] {
+            let expected = if cut < packet.len() + 8 {
+                packet.len() + 8
+            } else {
+                len
+            };
+            assert_eq!(
+                packet_extent(&with_extra[..cut], false),
+                Ok(PacketExtent::Partial(expected))
+            );
+            assert_eq!(
+                packet_extent(&with_extra[..cut], true),
+                Ok(PacketExtent::Complete(packet.len()))
+            );
+        }
+
+        // headが途中で切れている
+        let head_len = 8 + u32::from_le_bytes(packet[..4].try_into().unwrap()) as usize;
+        assert_eq!(
+            packet_extent(&packet[..20], true),
+            Ok(PacketExtent::Partial(head_len))
+        );
+    }
+
+    #[test]
+    fn test_parse_all_lossy() {
+        // 2つ目のpacketのtranを壊す
+        let mut broken = frame_packet(2);
+        let len = broken.len();
+        broken[len - 36..len - 32].copy_from_slice(&20u32.to_le_bytes());
+        let garbage = [0xff; 5];
+        let raw = [
+            frame_packet(1),
+            broken.clone(),
+            garbage.to_vec(),
+            frame_packet(3),
+            frame_packet(4)[..30].to_vec(),
+        ]
+        .concat();
+
+        let results = parse_all_lossy(&raw);
+
+        assert_eq!(results.len(), 4);
+        let mut results = results.into_iter();
+        assert_eq!(num(results.next().unwrap().unwrap()), 1);
+        let (offset, _) = results.next().unwrap().unwrap_err();
+        assert_eq!(offset, frame_packet(1).len());
+        assert_eq!(num(results.next().unwrap().unwrap()), 3);
+        let (offset, _) = results.next().unwrap().unwrap_err();
+        assert_eq!(offset, raw.len() - 30);
+
+        assert!(parse_all_lossy(&[]).is_empty());
+        assert_eq!(parse_all_lossy(&garbage).len(), 1);
+    }
+
+    #[test]
+    fn test_values() {
+        let raw = frame_packet(1);
+
+        let names: Vec<&str> = values(&raw).map(|value| value.unwrap().name).collect();
+        assert_eq!(names, ["head", "sndf", "fram"]);
+
+        let fram = values(&raw).nth(2).unwrap().unwrap();
+        let inner: Vec<(&str, usize)> = fram
+            .values()
+            .map(|value| value.unwrap())
+            .map(|value| (value.name, value.offset))
+            .collect();
+        let start = fram.offset + 8;
+        assert_eq!(
+            inner,
+            [("fnum", start), ("time", start + 12), ("btrs", start + 24)]
+        );
+        // 同じ場所を指している
+        assert_eq!(&raw[start + 4..start + 8], b"fnum");
+
+        let mut truncated = values(&raw[..raw.len() - 4]);
+        assert_eq!(truncated.next().unwrap().unwrap().name, "head");
+        assert_eq!(truncated.next().unwrap().unwrap().name, "sndf");
+        assert!(matches!(
+            truncated.next(),
+            Some(Err(ParseError::Truncated { .. }))
+        ));
+        assert!(truncated.next().is_none());
+    }
+}
@@ -0,0 +1,120 @@
+//! JSON helpers for frames, enabled by the `json` feature.
+//!
+//! The keys are the names of the sections in the packets, so that the JSON has the same
+//! shape as the data sent by mocopi. A frame packet looks like this, and a skeleton packet
+//! has `skdf` with `bons` of `{ "bnid", "pbid", "tran" }` instead of `fram`:
+//!
+//! ```json
+//! {
+//!   "head": { "ftyp": "sony motion format", "vrsn": 1 },
+//!   "sndf": { "ipad": "192.168.10.2", "rcvp": 12351 },
+//!   "fram": {
+//!     "fnum": 42,
+//!     "time": 1000,
+//!     "btrs": [
+//!       {
+//!         "bnid": 0,
+//!         "tran": {
+//!           "rot": { "x": 0.0, "y": 0.0, "z": 0.0, "w": 1.0 },
+//!           "pos": { "x": 0.0, "y": 0.0, "z": 0.0 }
+//!         }
+//!       }
+//!     ]
+//!   },
+//!   "extra": []
+//! }
+//! ```
+//!
+//! `head` also has `ver_minor` when `vrsn` has two bytes.
+//!
+//! This shape is [`SCHEMA_VERSION`], and a change to it bumps the version.
+
+use crate::FramePacket;
+use alloc::string::String;
+
+/// The version of the JSON shape described in the [module documentation](self).
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Serialize a frame packet into JSON.
+pub fn frame_to_json(frame: &FramePacket) -> Result<String, serde_json::Error> {
+    serde_json::to_string(frame)
+}
+
+/// Deserialize a frame packet from JSON written by [`frame_to_json`].
+pub fn frame_from_json(json: &str) -> Result<FramePacket, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{head, info};
+    use crate::{BoneTrans, Frame, Info, Position, Rotation, Transform};
+    use core::net::Ipv4Addr;
+
+    #[test]
+    fn test_json_round_trip() {
+        let packet = FramePacket {
+            head: head(),
+            info: info(),
+            frame: Frame {
+                num: 42,
+                time: 1000,
+                bones: vec![BoneTrans {
+                    id: 3,
+                    trans: Transform {
+                        rot: Rotation {
+                            x: 0.1,
+                            y: -0.2,
+                            z: 0.3,
+                            w: 0.9,
+                        },
+                        pos: Position {
+                            x: -0.008016131,
+                            y: 1.5,
+                            z: 1e-20,
+                        },
+                    },
+                }],
+            },
+            extra: Vec::new(),
+        };
+
+        let json = frame_to_json(&packet).unwrap();
+        let parsed = frame_from_json(&json).unwrap();
+
+        assert_eq!(parsed, packet);
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let packet = FramePacket {
+            head: head(),
+            info: Info {
+                addr: Ipv4Addr::new(192, 168, 10, 2).into(),
+                port: 12351,
+            },
+            frame: Frame {
+                num: 42,
+                time: 1000,
+                bones: vec![BoneTrans {
+                    id: 0,
+                    trans: Transform::identity(),
+                }],
+            },
+            extra: Vec::new(),
+        };
+
+        assert_eq!(
+            frame_to_json(&packet).unwrap(),
+            concat!(
+                r#"{"head":{"ftyp":"sony motion format","vrsn":1},"#,
+                r#""sndf":{"ipad":"192.168.10.2","rcvp":12351},"#,
+                r#""fram":{"fnum":42,"time":1000,"btrs":[{"bnid":0,"tran":{"#,
+                r#""rot":{"x":0.0,"y":0.0,"z":0.0,"w":1.0},"#,
+                r#""pos":{"x":0.0,"y":0.0,"z":0.0}}}]},"#,
+                r#""extra":[]}"#
+            )
+        );
+    }
+}
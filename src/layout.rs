@@ -0,0 +1,122 @@
+use crate::{parse_value_with, tags, Data, ParseError, ParseOptions, SkeletonOrFrame};
+
+/// The lengths declared by the sections of a packet, read by [`section_lengths`].
+///
+/// Like in the packet, the lengths do not include the length and name of each section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectionLengths {
+    /// The length of `head`.
+    pub head: u32,
+    /// The length of `sndf`.
+    pub info: u32,
+    /// The length of `skdf` or `fram`.
+    pub body: u32,
+    /// The length of `bons` or `btrs`.
+    pub bones: u32,
+    /// The number of sections in `bons` or `btrs`.
+    pub bone_count: usize,
+}
+
+impl SectionLengths {
+    /// Check that the packet has as many bones as the sections declared.
+    pub fn check(&self, packet: &SkeletonOrFrame) -> Result<(), ParseError> {
+        let parsed = match packet {
+            SkeletonOrFrame::Skeleton(packet) => packet.skeleton.bones.len(),
+            SkeletonOrFrame::Frame(packet) => packet.frame.bones.len(),
+        };
+        if parsed != self.bone_count {
+            return Err(ParseError::BoneCountMismatch {
+                declared: self.bone_count,
+                parsed,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Read the lengths of the sections of a packet without parsing their values.
+pub fn section_lengths(data: &[u8]) -> Result<SectionLengths, ParseError> {
+    let head = parse_value_with(data, 0, &ParseOptions::default())?;
+    let info = next(&head)?;
+    let body = next(&info)?;
+
+    // skdfはbonsだけを、framはfnumとtimeの後にbtrsを持つ
+    let bones = match body.name {
+        tags::SKDF => first(&body)?,
+        tags::FRAM => {
+            let num = first(&body)?;
+            let time = next(&num)?;
+            next(&time)?
+        }
+        name => return Err(ParseError::UnknownPacketType(name.into())),
+    };
+
+    let mut bone_count = 0;
+    let mut remain = bones.data;
+    let mut offset = bones.data_offset();
+    while !remain.is_empty() {
+        let bone = parse_value_with(remain, offset, &ParseOptions::default())?;
+        remain = bone.rem;
+        offset = bone.rem_offset();
+        bone_count += 1;
+    }
+
+    Ok(SectionLengths {
+        head: head.len,
+        info: info.len,
+        body: body.len,
+        bones: bones.len,
+        bone_count,
+    })
+}
+
+/// Parse the value after `value`.
+fn next<'a>(value: &Data<'a>) -> Result<Data<'a>, ParseError> {
+    parse_value_with(value.rem, value.rem_offset(), &ParseOptions::default())
+}
+
+/// Parse the first value in the data of `value`.
+fn first<'a>(value: &Data<'a>) -> Result<Data<'a>, ParseError> {
+    parse_value_with(value.data, value.data_offset(), &ParseOptions::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode_skeleton, parse, SkeletonBuilder, Transform, ROOT_PARENT};
+
+    #[test]
+    fn test_section_lengths() {
+        let packet = SkeletonBuilder::new()
+            .add_bone(0, ROOT_PARENT, Transform::identity())
+            .add_bone(1, 0, Transform::identity())
+            .build()
+            .unwrap();
+        let raw = encode_skeleton(&packet).unwrap();
+
+        let lens = section_lengths(&raw).unwrap();
+
+        assert_eq!(lens.bone_count, 2);
+        // bndtはbnid, pbid, tranの3つのsectionを持つ
+        assert_eq!(lens.bones, 2 * (8 + 10 + 10 + 36));
+        assert_eq!(lens.body, 8 + lens.bones);
+        assert_eq!(
+            8 * 3 + (lens.head + lens.info + lens.body) as usize,
+            raw.len()
+        );
+
+        let mut parsed = parse(&raw).unwrap();
+        assert_eq!(lens.check(&parsed), Ok(()));
+
+        if let SkeletonOrFrame::Skeleton(packet) = &mut parsed {
+            packet.skeleton.bones.pop();
+        }
+        assert_eq!(
+            lens.check(&parsed),
+            Err(ParseError::BoneCountMismatch {
+                declared: 2,
+                parsed: 1
+            })
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{
+    frame_section, parse_btdt, parse_frame_header, parse_tagged, tags, BoneTrans, ParseError,
+    ParseOptions, Version,
+};
+
+/// A frame whose bones are parsed from the packet only when they are read, returned by
+/// [`parse_frame_lazy`].
+#[derive(Debug, Clone)]
+pub struct FrameRef<'a> {
+    pub num: u32,
+    /// The time since mocopi started sending, in milliseconds.
+    pub time: u32,
+    /// The data of `btrs`.
+    btrs: &'a [u8],
+    /// Where `btrs` starts, counted from the start of the packet.
+    offset: usize,
+    opts: ParseOptions,
+}
+
+impl<'a> FrameRef<'a> {
+    /// Iterate over the bones, parsing each `btdt` when the iterator is advanced.
+    pub fn bones(&self) -> BoneIter<'a> {
+        BoneIter {
+            remain: self.btrs,
+            offset: self.offset,
+            opts: self.opts,
+        }
+    }
+}
+
+/// An iterator over the bones of a frame, created by [`FrameRef::bones`].
+#[derive(Debug, Clone)]
+pub struct BoneIter<'a> {
+    remain: &'a [u8],
+    offset: usize,
+    opts: ParseOptions,
+}
+
+impl Iterator for BoneIter<'_> {
+    type Item = Result<BoneTrans, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remain.is_empty() {
+            return None;
+        }
+
+        match parse_btdt(self.remain, self.offset, &self.opts) {
+            Ok((len, bone)) => {
+                // btdtの後ろの値も長さの分だけ読み飛ばす
+                let size = len as usize + 8;
+                self.remain = &self.remain[size..];
+                self.offset += size;
+                Some(Ok(bone))
+            }
+            Err(e) => {
+                // 区切りが読めないので終わる
+                self.remain = &[];
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Read the header of a frame packet and return a frame whose bones are parsed on demand.
+///
+/// This avoids parsing all the bones when only a few of them are needed. The bones are
+/// checked only when they are read, so an error in `btrs` is returned by the iterator.
+/// Returns [`ParseError::UnexpectedTag`] if the packet is not a frame packet.
+///
+/// # Examples
+///
+/// ```no_run
+/// let buf: Vec<u8> = std::fs::read("frame.bin").unwrap();
+/// let frame = mocopi_parser::parse_frame_lazy(&buf).unwrap();
+/// let head = frame.bones().find(|bone| matches!(bone, Ok(bone) if bone.id == 10));
+/// ```
+pub fn parse_frame_lazy(data: &[u8]) -> Result<FrameRef<'_>, ParseError> {
+    let opts = ParseOptions::default();
+    let (version, frame) = frame_section(data, &opts)?;
+    match version {
+        Version::V1 => {
+            let (num, time, rem) = parse_frame_header(frame.data, frame.data_offset(), &opts)?;
+            let btrs = parse_tagged(rem, frame.rem_offset() - rem.len(), tags::BTRS, &opts)?;
+            Ok(FrameRef {
+                num,
+                time,
+                btrs: btrs.data,
+                offset: btrs.data_offset(),
+                opts,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{head, info};
+    use crate::{encode_frame, encode_skeleton, Frame, FramePacket, SkeletonBuilder, Transform};
+    use alloc::vec::Vec;
+
+    fn frame_packet() -> FramePacket {
+        FramePacket {
+            head: head(),
+            info: info(),
+            frame: Frame {
+                num: 42,
+                time: 1000,
+                bones: (0..3)
+                    .map(|id| BoneTrans {
+                        id,
+                        trans: Transform::identity(),
+                    })
+                    .collect(),
+            },
+            extra: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_frame_lazy() {
+        let packet = frame_packet();
+        let raw = encode_frame(&packet).unwrap();
+
+        let frame = parse_frame_lazy(&raw).unwrap();
+        assert_eq!(frame.num, 42);
+        assert_eq!(frame.time, 1000);
+        let bones: Vec<BoneTrans> = frame.bones().collect::<Result<_, _>>().unwrap();
+        assert_eq!(bones, packet.frame.bones);
+        assert_eq!(frame.bones().nth(1).unwrap().unwrap().id, 1);
+
+        let skeleton = SkeletonBuilder::new()
+            .add_bone(0, crate::ROOT_PARENT, Transform::identity())
+            .build()
+            .unwrap();
+        let raw = encode_skeleton(&skeleton).unwrap();
+        assert!(matches!(
+            parse_frame_lazy(&raw).unwrap_err(),
+            ParseError::UnexpectedTag {
+                expected: "fram",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_frame_lazy_corrupted() {
+        let packet = frame_packet();
+        let mut raw = encode_frame(&packet).unwrap();
+
+        // 2つ目のbtdtのnameを壊す
+        let pos = raw
+            .windows(4)
+            .enumerate()
+            .filter(|(_, w)| w == b"btdt")
+            .nth(1)
+            .unwrap()
+            .0;
+        raw[pos..pos + 4].copy_from_slice(b"xxxx");
+
+        let frame = parse_frame_lazy(&raw).unwrap();
+        let mut bones = frame.bones();
+        assert!(bones.next().unwrap().is_ok());
+        assert!(matches!(
+            bones.next().unwrap(),
+            Err(ParseError::UnexpectedTag {
+                expected: "btdt",
+                ..
+            })
+        ));
+        assert!(bones.next().is_none());
+    }
+}
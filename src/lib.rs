@@ -1,70 +1,231 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "alloc"))]
+compile_error!("the `alloc` feature is required");
+
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use nom::bytes::complete::take;
 use nom::error::Error;
-use nom::number::complete::le_u32;
+use nom::number::complete::u32 as nom_u32;
 use serde::{Deserialize, Serialize};
-use std::error;
+
+pub mod bone;
+mod builder;
+mod coord;
+mod decimate;
+pub mod encode;
+mod endian;
+pub mod error;
+pub mod export;
+mod float;
+mod frame;
+#[cfg(feature = "glam")]
+pub mod glam;
+mod iter;
+#[cfg(feature = "json")]
+pub mod json;
+mod layout;
+mod lazy;
+mod math;
+mod mirror;
+#[cfg(feature = "nalgebra")]
+pub mod nalgebra;
+mod options;
+mod pose;
+mod rate;
+#[cfg(feature = "std")]
+mod reader;
+mod reassembler;
+#[cfg(feature = "std")]
+pub mod record;
+#[cfg(feature = "tokio")]
+mod recv;
+#[cfg(feature = "std")]
+mod remap;
+#[cfg(feature = "rerun")]
+pub mod rerun;
+mod resample;
+mod skeleton;
+mod smooth;
+pub mod tags;
+#[cfg(test)]
+mod test_util;
+mod version;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use bone::BoneName;
+pub use builder::SkeletonBuilder;
+pub use decimate::Decimator;
+pub use encode::{encode_frame, encode_skeleton, encoded_len};
+pub use endian::Endian;
+#[cfg(feature = "rerun")]
+pub use error::LogError;
+pub use error::{EncodeError, ExportError, ImportError, ParseError, SkeletonError};
+pub use frame::{frame_delta, frames_are_still, lerp_frame, BoneDelta, FrameStats, UNIT_TOLERANCE};
+pub use iter::{iter_packets, parse_all_lossy, parse_many, values, PacketIter, ValueIter};
+pub use layout::{section_lengths, SectionLengths};
+pub use lazy::{parse_frame_lazy, BoneIter, FrameRef};
+pub use mirror::mirror_frame;
+pub use options::ParseOptions;
+pub use pose::JointPose;
+pub use rate::FrameRateEstimator;
+#[cfg(feature = "std")]
+pub use reader::{parse_reader, PacketReader};
+pub use reassembler::Reassembler;
+#[cfg(feature = "tokio")]
+pub use recv::recv_parse;
+#[cfg(feature = "std")]
+pub use remap::{remap_frame, remap_skeleton};
+pub use resample::Resampler;
+pub use skeleton::BoneIndex;
+pub use smooth::Smoother;
+pub use version::{Version, VersionNumber};
 
 pub type BoneId = u16;
 pub type TransVal = f32;
+/// The quaternion in a [`Transform`], for code which reads better with the math name.
+pub type Quaternion = Rotation;
+
+/// The format of the packets sent by mocopi, stored in [`Head::format`].
+pub const EXPECTED_FORMAT: &str = "sony motion format";
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// The parent ID of the root bone.
+pub const ROOT_PARENT: BoneId = 0xffff;
+
+/// The most bones which a skeleton or a frame may have unless another limit is given to
+/// [`parse_with_max_bones`] or [`ParseOptions::max_bones`].
+pub const DEFAULT_MAX_BONES: usize = 256;
+
+/// The shortest data which can be a packet: the length and name of its three sections.
+pub const MIN_PACKET_LEN: usize = 3 * 8;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SkeletonPacket {
     pub head: Head,
+    #[serde(rename = "sndf")]
     pub info: Info,
+    #[serde(rename = "skdf")]
     pub skeleton: Skeleton,
+    /// The sections after `skdf` as `(name, data)`, which this crate does not know.
+    #[serde(default)]
+    pub extra: Vec<(String, Vec<u8>)>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Head {
+    #[serde(rename = "ftyp")]
     pub format: String,
+    /// The version, or its major part when `vrsn` has two bytes.
+    #[serde(rename = "vrsn")]
     pub ver: u8,
+    /// The minor part of the version when `vrsn` has two bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ver_minor: Option<u8>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Info {
-    pub addr: u64,
+    /// The address of the sender. mocopi sends IPv4 addresses, padded to 8 bytes.
+    #[serde(rename = "ipad")]
+    pub addr: IpAddr,
+    #[serde(rename = "rcvp")]
     pub port: u16,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+impl Info {
+    /// The address of the sender.
+    ///
+    /// The bytes of `ipad` are the octets in order, so `c0 a8 0a 02` is `192.168.10.2`. Read
+    /// as a little-endian `u64`, the first octet is the lowest byte.
+    pub fn ip(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// The address of the sender combined with `rcvp`.
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.addr, self.port)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Skeleton {
+    #[serde(rename = "bons")]
     pub bones: Vec<Bone>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Bone {
+    #[serde(rename = "bnid")]
     pub id: BoneId,
+    #[serde(rename = "pbid")]
     pub parent: BoneId,
+    #[serde(rename = "tran")]
     pub trans: Transform,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+impl Bone {
+    /// The name of the bone, if the ID belongs to the mocopi skeleton.
+    pub fn name(&self) -> Option<BoneName> {
+        BoneName::try_from(self.id).ok()
+    }
+
+    /// Whether the bone is a root, i.e. its parent is [`ROOT_PARENT`] or itself.
+    pub fn is_root(&self) -> bool {
+        self.parent == ROOT_PARENT || self.parent == self.id
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FramePacket {
     pub head: Head,
+    #[serde(rename = "sndf")]
     pub info: Info,
+    #[serde(rename = "fram")]
     pub frame: Frame,
+    /// The sections after `fram` as `(name, data)`, which this crate does not know.
+    #[serde(default)]
+    pub extra: Vec<(String, Vec<u8>)>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Frame {
+    #[serde(rename = "fnum")]
     pub num: u32,
+    /// The time since mocopi started sending, in milliseconds.
     pub time: u32,
+    #[serde(rename = "btrs")]
     pub bones: Vec<BoneTrans>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// A frame whose bones borrow a buffer owned by the caller, returned by [`parse_into`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameView<'a> {
+    pub num: u32,
+    /// The time since mocopi started sending, in milliseconds.
+    pub time: u32,
+    pub bones: &'a [BoneTrans],
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BoneTrans {
+    #[serde(rename = "bnid")]
     pub id: BoneId,
+    #[serde(rename = "tran")]
     pub trans: Transform,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Transform {
     pub rot: Rotation,
     pub pos: Position,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Rotation {
     pub x: TransVal,
     pub y: TransVal,
@@ -72,39 +233,176 @@ pub struct Rotation {
     pub w: TransVal,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Position {
     pub x: TransVal,
     pub y: TransVal,
     pub z: TransVal,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// A value in the streamed data, returned by [`parse_value`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Data<'a> {
+    /// Where the value starts, counted from the start of the packet, or of the buffer passed
+    /// to [`parse_value`] or [`values`].
+    pub offset: usize,
+    /// The length of `data`.
     pub len: u32,
-    pub name: String,
+    /// The 4-byte name of the value, e.g. `head`.
+    pub name: &'a str,
     pub data: &'a [u8],
+    /// The data after the value.
     pub rem: &'a [u8],
 }
 
+impl Data<'_> {
+    /// Where `data` starts, counted from the start of the packet.
+    pub(crate) fn data_offset(&self) -> usize {
+        self.offset + 8
+    }
+
+    /// Where `rem` starts, counted from the start of the packet.
+    pub(crate) fn rem_offset(&self) -> usize {
+        self.offset + 8 + self.len as usize
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum SkeletonOrFrame {
     Skeleton(SkeletonPacket),
     Frame(FramePacket),
 }
 
-/// Parse the values.
-fn parse_value(data: &[u8]) -> Result<Data, Box<dyn error::Error + '_>> {
+impl SkeletonOrFrame {
+    /// The skeleton packet, if this is a skeleton.
+    pub fn as_skeleton(&self) -> Option<&SkeletonPacket> {
+        match self {
+            SkeletonOrFrame::Skeleton(packet) => Some(packet),
+            SkeletonOrFrame::Frame(_) => None,
+        }
+    }
+
+    /// The frame packet, if this is a frame.
+    pub fn as_frame(&self) -> Option<&FramePacket> {
+        match self {
+            SkeletonOrFrame::Skeleton(_) => None,
+            SkeletonOrFrame::Frame(packet) => Some(packet),
+        }
+    }
+
+    /// Whether this is a skeleton.
+    pub fn is_skeleton(&self) -> bool {
+        matches!(self, SkeletonOrFrame::Skeleton(_))
+    }
+
+    /// Whether this is a frame.
+    pub fn is_frame(&self) -> bool {
+        matches!(self, SkeletonOrFrame::Frame(_))
+    }
+}
+
+/// The type of a packet, returned by [`packet_kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PacketKind {
+    Skeleton,
+    Frame,
+}
+
+/// Parse a packet with [`parse`].
+impl<'a> TryFrom<&'a [u8]> for SkeletonOrFrame {
+    type Error = ParseError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        parse(data)
+    }
+}
+
+impl From<SkeletonPacket> for SkeletonOrFrame {
+    fn from(packet: SkeletonPacket) -> Self {
+        SkeletonOrFrame::Skeleton(packet)
+    }
+}
+
+impl From<FramePacket> for SkeletonOrFrame {
+    fn from(packet: FramePacket) -> Self {
+        SkeletonOrFrame::Frame(packet)
+    }
+}
+
+/// Take the skeleton packet, or return the packet unchanged if it is a frame.
+impl TryFrom<SkeletonOrFrame> for SkeletonPacket {
+    type Error = SkeletonOrFrame;
+
+    fn try_from(packet: SkeletonOrFrame) -> Result<Self, Self::Error> {
+        match packet {
+            SkeletonOrFrame::Skeleton(packet) => Ok(packet),
+            packet => Err(packet),
+        }
+    }
+}
+
+/// Take the frame packet, or return the packet unchanged if it is a skeleton.
+impl TryFrom<SkeletonOrFrame> for FramePacket {
+    type Error = SkeletonOrFrame;
+
+    fn try_from(packet: SkeletonOrFrame) -> Result<Self, Self::Error> {
+        match packet {
+            SkeletonOrFrame::Frame(packet) => Ok(packet),
+            packet => Err(packet),
+        }
+    }
+}
+
+/// Parse the value at the start of the data: its length, name and data.
+///
+/// This is the primitive which the packets are built from. Use [`values`] to walk all the
+/// values in a buffer.
+pub fn parse_value(data: &[u8]) -> Result<Data<'_>, ParseError> {
+    parse_value_with(data, 0, &ParseOptions::default())
+}
+
+/// Parse the value at `offset` from the start of the packet.
+fn parse_value_with<'a>(
+    data: &'a [u8],
+    offset: usize,
+    opts: &ParseOptions,
+) -> Result<Data<'a>, ParseError> {
     // lengthの長さは4bytesで固定
-    let (data, length) = le_u32::<_, Error<_>>(data)? as (&[u8], u32);
+    let (data, length) = nom_u32::<_, Error<_>>(opts.endian.into())(data).map_err(|_| {
+        ParseError::UnexpectedEof {
+            offset,
+            expected: 4,
+            found: data.len(),
+        }
+    })?;
 
     // nameは4bytesの文字列
-    let (data, name) = take::<_, _, Error<_>>(4usize)(data)?;
-    let name_str = String::from_utf8(name.to_vec())?;
+    let (data, name) =
+        take::<_, _, Error<_>>(4usize)(data).map_err(|_| ParseError::UnexpectedEof {
+            offset,
+            expected: 4,
+            found: data.len(),
+        })?;
+    let name_str = if opts.lenient_names {
+        // 表示できるASCIIの部分だけをnameとする
+        let end = name
+            .iter()
+            .position(|b| !b.is_ascii_graphic())
+            .unwrap_or(name.len());
+        core::str::from_utf8(&name[..end])?
+    } else {
+        core::str::from_utf8(name)?
+    };
 
     // valueの長さはlengthの値による
-    let (rem, data) = take::<_, _, Error<_>>(length)(data)?;
+    let (rem, data) = take::<_, _, Error<_>>(length)(data).map_err(|_| ParseError::Truncated {
+        offset,
+        needed: length as usize,
+        available: data.len(),
+    })?;
 
     Ok(Data {
+        offset,
         len: length,
         name: name_str,
         data,
@@ -112,152 +410,304 @@ fn parse_value(data: &[u8]) -> Result<Data, Box<dyn error::Error + '_>> {
     })
 }
 
-fn parse_head(data: &[u8]) -> Result<(u32, Head), Box<dyn error::Error + '_>> {
-    let data = parse_value(data)?;
+/// Parse the value and check that its name is `tag`.
+fn parse_tagged<'a>(
+    data: &'a [u8],
+    offset: usize,
+    tag: &'static str,
+    opts: &ParseOptions,
+) -> Result<Data<'a>, ParseError> {
+    let data = parse_value_with(data, offset, opts)?;
+    if !tag_matches(data.name, tag, opts) {
+        return Err(ParseError::UnexpectedTag {
+            offset,
+            expected: tag,
+            found: data.name.to_string(),
+        });
+    }
+    Ok(data)
+}
+
+/// Whether the name of a value is `tag`.
+///
+/// With [`ParseOptions::lenient_names`], a name which was cut off also matches if it still has
+/// at least 3 bytes, so that a name such as `t` is not taken for both `tran` and `time`.
+fn tag_matches(name: &str, tag: &str, opts: &ParseOptions) -> bool {
+    if opts.lenient_names {
+        name.len() >= 3 && tag.starts_with(name)
+    } else {
+        name == tag
+    }
+}
+
+fn parse_head(data: &[u8], offset: usize, opts: &ParseOptions) -> Result<(u32, Head), ParseError> {
+    let data = parse_tagged(data, offset, tags::HEAD, opts)?;
     let len = data.len;
 
     // ftyp
-    let data = parse_value(data.data)?;
+    let data = parse_tagged(data.data, data.data_offset(), tags::FTYP, opts)?;
     let format = String::from_utf8(data.data.to_vec())?;
 
     // vrsn
-    let data = parse_value(data.rem)?;
-    let ver = data.data[0];
+    let data = parse_tagged(data.rem, data.rem_offset(), tags::VRSN, opts)?;
+    let (ver, ver_minor) = parse_version(data.data)?;
+
+    Ok((
+        len,
+        Head {
+            format,
+            ver,
+            ver_minor,
+        },
+    ))
+}
 
-    Ok((len, Head { format, ver }))
+/// Parse `vrsn`, which is 1 byte or 2 bytes of major and minor.
+fn parse_version(data: &[u8]) -> Result<(u8, Option<u8>), ParseError> {
+    match *data {
+        [ver] => Ok((ver, None)),
+        [major, minor] => Ok((major, Some(minor))),
+        _ => Err(ParseError::BadLength),
+    }
 }
 
-fn parse_info(data: &[u8]) -> Result<(u32, Info), Box<dyn error::Error + '_>> {
-    let data = parse_value(data)?;
+fn parse_info(data: &[u8], offset: usize, opts: &ParseOptions) -> Result<(u32, Info), ParseError> {
+    let data = parse_tagged(data, offset, tags::SNDF, opts)?;
     let len = data.len;
 
     // ipad
-    let data = parse_value(data.data)?;
-    let addr = u64::from_le_bytes(data.data.try_into()?);
+    let data = parse_tagged(data.data, data.data_offset(), tags::IPAD, opts)?;
+    let addr = parse_addr(data.data)?;
 
     // rcvp
-    let data = parse_value(data.rem)?;
-    let port = u16::from_le_bytes(data.data.try_into()?);
+    let data = parse_tagged(data.rem, data.rem_offset(), tags::RCVP, opts)?;
+    let port = opts.endian.u16(data.data)?;
 
     Ok((len, Info { addr, port }))
 }
 
-fn parse_skeleton(data: &[u8]) -> Result<(u32, Skeleton), Box<dyn error::Error + '_>> {
+/// Parse an IPv4 address of 4 or 8 bytes, or an IPv6 address of 16 bytes.
+fn parse_addr(data: &[u8]) -> Result<IpAddr, ParseError> {
+    match data.len() {
+        // 8bytesのときは後ろの4bytesが0で埋められている
+        4 | 8 => Ok(Ipv4Addr::from(<[u8; 4]>::try_from(&data[..4])?).into()),
+        16 => Ok(Ipv6Addr::from(<[u8; 16]>::try_from(data)?).into()),
+        len => Err(ParseError::BadAddrLen(len)),
+    }
+}
+
+fn parse_skeleton(
+    data: &[u8],
+    offset: usize,
+    opts: &ParseOptions,
+) -> Result<(u32, Skeleton), ParseError> {
     // skdf
-    let data = parse_value(data)?;
+    let data = parse_tagged(data, offset, tags::SKDF, opts)?;
     let len = data.len;
 
     // bons
-    let (_, bones) = parse_bones(data.data)?;
+    let (_, bones) = parse_bones(data.data, data.data_offset(), opts)?;
 
-    Ok((len, Skeleton { bones: *bones }))
+    Ok((len, Skeleton { bones }))
 }
 
-fn parse_frame(data: &[u8]) -> Result<(u32, Frame), Box<dyn error::Error + '_>> {
+fn parse_frame(
+    data: &[u8],
+    offset: usize,
+    opts: &ParseOptions,
+) -> Result<(u32, Frame), ParseError> {
     // fram
-    let data = parse_value(data)?;
+    let data = parse_tagged(data, offset, tags::FRAM, opts)?;
     let len = data.len;
 
+    let (num, time, rem) = parse_frame_header(data.data, data.data_offset(), opts)?;
+
+    // btrs, remはframのdataの末尾にある
+    let (_, bones) = parse_bone_trans(rem, data.rem_offset() - rem.len(), opts)?;
+
+    Ok((len, Frame { num, time, bones }))
+}
+
+/// Parse `fnum` and `time` at the start of the data of `fram`, returning the rest.
+fn parse_frame_header<'a>(
+    data: &'a [u8],
+    offset: usize,
+    opts: &ParseOptions,
+) -> Result<(u32, u32, &'a [u8]), ParseError> {
     // fnum
-    let data = parse_value(data.data)?;
-    let num = u32::from_le_bytes(data.data.try_into()?);
+    let data = parse_tagged(data, offset, tags::FNUM, opts)?;
+    let num = opts.endian.u32(data.data)?;
 
     // time
-    let data = parse_value(data.rem)?;
-    let time = u32::from_le_bytes(data.data.try_into()?);
+    let data = parse_tagged(data.rem, data.rem_offset(), tags::TIME, opts)?;
+    let time = opts.endian.u32(data.data)?;
 
-    // btrs
-    let (_, bones) = parse_bone_trans(data.rem)?;
+    Ok((num, time, data.rem))
+}
 
-    Ok((
-        len,
-        Frame {
-            num,
-            time,
-            bones: *bones,
-        },
-    ))
+fn parse_bone_trans(
+    data: &[u8],
+    offset: usize,
+    opts: &ParseOptions,
+) -> Result<(u32, Vec<BoneTrans>), ParseError> {
+    let mut bones = Vec::new();
+    let len = parse_bone_trans_into(data, offset, opts, &mut bones)?;
+    Ok((len, bones))
 }
 
-fn parse_bone_trans(data: &[u8]) -> Result<(u32, Box<Vec<BoneTrans>>), Box<dyn error::Error + '_>> {
+/// Parse `btrs` and push its bones to `bones`, returning its length.
+///
+/// Returns [`ParseError::TooManyBones`] if `btrs` has more than `opts.max_bones` bones.
+fn parse_bone_trans_into(
+    data: &[u8],
+    offset: usize,
+    opts: &ParseOptions,
+    bones: &mut Vec<BoneTrans>,
+) -> Result<u32, ParseError> {
     // btrs
-    let btrs_data = parse_value(data)?;
+    let btrs_data = parse_tagged(data, offset, tags::BTRS, opts)?;
     let btrs_len = btrs_data.len;
+    if btrs_len == 0 {
+        return Ok(0);
+    }
 
     // btrsの下にあるbtdtをparseしていく
-    let mut bones: Vec<BoneTrans> = Vec::new();
     let mut read_bytes: u32 = 0;
     loop {
-        let part = &btrs_data.data[(read_bytes as usize)..];
-
-        // btdt
-        let data = parse_value(part)?;
-        let len = data.len;
-
-        // bnid
-        let data = parse_value(data.data)?;
-        let id = u16::from_le_bytes(data.data.try_into()?);
-
-        // tran
-        let (_, trans) = parse_trans(data.rem)?;
+        let part = btrs_data
+            .data
+            .get((read_bytes as usize)..)
+            .ok_or(ParseError::BadLength)?;
+
+        // 巨大なlengthで延々と読まされないように数を制限する
+        if bones.len() >= opts.max_bones {
+            return Err(ParseError::TooManyBones {
+                max: opts.max_bones,
+            });
+        }
 
-        bones.push(BoneTrans { id, trans });
+        let (len, bone) = parse_btdt(part, btrs_data.data_offset() + read_bytes as usize, opts)?;
+        bones.push(bone);
 
+        // mocopiのbtdtはbnidとtranだけを持つが、後ろに値があっても長さの分だけ読み飛ばす
         read_bytes += len + 8;
-        if read_bytes == btrs_len {
+        if read_bytes >= btrs_len {
+            if read_bytes > btrs_len {
+                return Err(ParseError::BadLength);
+            }
             break;
         }
     }
 
-    Ok((btrs_len, Box::new(bones)))
+    Ok(btrs_len)
 }
 
-fn parse_bones(data: &[u8]) -> Result<(u32, Box<Vec<Bone>>), Box<dyn error::Error + '_>> {
+/// Parse a `btdt` in `btrs`, returning its length and the bone.
+fn parse_btdt(
+    data: &[u8],
+    offset: usize,
+    opts: &ParseOptions,
+) -> Result<(u32, BoneTrans), ParseError> {
+    // btdt
+    let data = parse_tagged(data, offset, tags::BTDT, opts)?;
+    let len = data.len;
+
+    // bnid
+    let data = parse_tagged(data.data, data.data_offset(), tags::BNID, opts)?;
+    let id = opts.endian.u16(data.data)?;
+
+    // tran
+    let (_, trans) = parse_trans_with(data.rem, data.rem_offset(), opts)?;
+
+    Ok((len, BoneTrans { id, trans }))
+}
+
+/// Parse `bons`, returning [`ParseError::TooManyBones`] if it has more than `opts.max_bones`
+/// bones.
+fn parse_bones(
+    data: &[u8],
+    offset: usize,
+    opts: &ParseOptions,
+) -> Result<(u32, Vec<Bone>), ParseError> {
     // bons
-    let bons_data = parse_value(data)?;
+    let bons_data = parse_tagged(data, offset, tags::BONS, opts)?;
     let bons_len = bons_data.len;
+    if bons_len == 0 {
+        return Ok((0, Vec::new()));
+    }
 
     // bonsの下にあるbndtをparseしていく
     let mut bones: Vec<Bone> = Vec::new();
     let mut read_bytes: u32 = 0;
     loop {
-        let part = &bons_data.data[(read_bytes as usize)..];
+        let part = bons_data
+            .data
+            .get((read_bytes as usize)..)
+            .ok_or(ParseError::BadLength)?;
+
+        // btrsと同じく数を制限する
+        if bones.len() >= opts.max_bones {
+            return Err(ParseError::TooManyBones {
+                max: opts.max_bones,
+            });
+        }
 
         // bndt
-        let data = parse_value(part)?;
+        let data = parse_tagged(
+            part,
+            bons_data.data_offset() + read_bytes as usize,
+            tags::BNDT,
+            opts,
+        )?;
         let len = data.len;
 
         // bnid
-        let data = parse_value(data.data)?;
-        let id = u16::from_le_bytes(data.data.try_into()?);
+        let data = parse_tagged(data.data, data.data_offset(), tags::BNID, opts)?;
+        let id = opts.endian.u16(data.data)?;
 
         // pbid
-        let data = parse_value(data.rem)?;
-        let parent = u16::from_le_bytes(data.data.try_into()?);
+        let data = parse_tagged(data.rem, data.rem_offset(), tags::PBID, opts)?;
+        let parent = opts.endian.u16(data.data)?;
 
         // tran
-        let (_, trans) = parse_trans(part)?;
+        let (_, trans) = parse_trans_with(data.rem, data.rem_offset(), opts)?;
 
         bones.push(Bone { id, parent, trans });
 
+        // btdtと同じく、tranの後ろの値は読み飛ばす
         read_bytes += len + 8;
-        if read_bytes == bons_len {
+        if read_bytes >= bons_len {
+            if read_bytes > bons_len {
+                return Err(ParseError::BadLength);
+            }
             break;
         }
     }
 
-    Ok((bons_len, Box::new(bones)))
+    Ok((bons_len, bones))
+}
+
+#[doc(hidden)]
+pub fn parse_trans(data: &[u8]) -> Result<(u32, Transform), ParseError> {
+    parse_trans_with(data, 0, &ParseOptions::default())
 }
 
-fn parse_trans(data: &[u8]) -> Result<(u32, Transform), Box<dyn error::Error + '_>> {
+fn parse_trans_with(
+    data: &[u8],
+    offset: usize,
+    opts: &ParseOptions,
+) -> Result<(u32, Transform), ParseError> {
     // tran
-    let data = parse_value(data)?;
+    let data = parse_tagged(data, offset, tags::TRAN, opts)?;
+    if data.data.len() < 28 {
+        return Err(ParseError::BadLength);
+    }
 
     // 28bytesのデータを4bytesごとに取り出す
     let mut values = [0.0; 7];
     for (i, v) in values.iter_mut().enumerate() {
         let b = &data.data[i * 4..(i * 4 + 4)];
-        *v = f32::from_le_bytes(b.try_into()?);
+        *v = opts.endian.f32(b)?;
     }
 
     Ok((
@@ -282,15 +732,15 @@ fn parse_trans(data: &[u8]) -> Result<(u32, Transform), Box<dyn error::Error + '
 ///
 /// # Examples
 ///
-/// ```
+/// ```no_run
 /// use std::net::UdpSocket;
 ///
-/// let socket = UdpSocket::bind("192.168.10.1:12351").unwrap();
+/// let socket = UdpSocket::bind("0.0.0.0:12351").unwrap();
 /// let mut buf = [0; 1024];
 ///
 /// loop {
-///     socket.recv_from(&mut buf).unwrap();
-///     let packet = mocopi_parser::parse(&mut buf).unwrap();
+///     let len = socket.recv(&mut buf).unwrap();
+///     let packet = mocopi_parser::parse(&buf[..len]).unwrap();
 ///
 ///     match packet {
 ///         mocopi_parser::SkeletonOrFrame::Skeleton(skeleton) => { dbg!(skeleton); },
@@ -298,31 +748,412 @@ fn parse_trans(data: &[u8]) -> Result<(u32, Transform), Box<dyn error::Error + '
 ///     }
 /// }
 /// ```
-pub fn parse(data: &mut [u8]) -> Result<SkeletonOrFrame, Box<dyn error::Error + '_>> {
-    let (len, head) = parse_head(data)?;
-    let mut remain = &data[((len + 8) as usize)..];
-
-    let (len, info) = parse_info(remain)?;
-    remain = &remain[((len + 8) as usize)..];
-
-    let name = parse_value(data)?.name;
-
-    if name == "skdf" {
-        let (_, skeleton) = parse_skeleton(remain)?;
-        Ok(SkeletonOrFrame::Skeleton(SkeletonPacket {
-            head,
-            info,
-            skeleton,
-        }))
+pub fn parse(data: &[u8]) -> Result<SkeletonOrFrame, ParseError> {
+    parse_with_len(data).map(|(_, packet)| packet)
+}
+
+/// Parse the streamed data from mocopi and return the number of consumed bytes with the packet.
+///
+/// This is useful when the buffer holds several packets back to back.
+///
+/// # Examples
+///
+/// ```no_run
+/// let buf: Vec<u8> = std::fs::read("capture.bin").unwrap();
+/// let mut remain = &buf[..];
+///
+/// while !remain.is_empty() {
+///     let (len, packet) = mocopi_parser::parse_with_len(remain).unwrap();
+///     remain = &remain[len..];
+/// }
+/// ```
+pub fn parse_with_len(data: &[u8]) -> Result<(usize, SkeletonOrFrame), ParseError> {
+    parse_packet(data, &ParseOptions::default())
+}
+
+/// Parse a packet at the start of a buffer which may not have been fully received yet.
+///
+/// Returns [`ParseError::Incomplete`] with the number of bytes missing from the first section
+/// which is cut off, so that the caller can wait for them. This includes the sections after
+/// `skdf` or `fram`, and a name which is cut off after them, since it may be one of them.
+/// Otherwise this is the same as [`parse_with_len`].
+///
+/// A packet which ends right at the end of the buffer is parsed without waiting for the next
+/// packet, so its extra sections must arrive together with it.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io::Read;
+///
+/// let mut stream = std::net::TcpStream::connect("192.168.10.2:12351").unwrap();
+/// let mut buf = Vec::new();
+/// let mut chunk = [0; 1024];
+///
+/// loop {
+///     match mocopi_parser::parse_streaming(&buf) {
+///         Ok((len, packet)) => {
+///             buf.drain(..len);
+///             dbg!(packet);
+///         }
+///         Err(mocopi_parser::ParseError::Incomplete(_)) => {
+///             let len = stream.read(&mut chunk).unwrap();
+///             buf.extend_from_slice(&chunk[..len]);
+///         }
+///         Err(e) => panic!("{}", e),
+///     }
+/// }
+/// ```
+pub fn parse_streaming(data: &[u8]) -> Result<(usize, SkeletonOrFrame), ParseError> {
+    // head, info, skdfかframの3つのsectionが揃っているかを先に確かめる
+    let mut remain = data;
+    for _ in 0..3 {
+        remain = skip_value_streaming(remain)?;
+    }
+    // 後に続くsectionも揃うまで待つ
+    match iter::packet_extent(data, false)? {
+        iter::PacketExtent::Partial(len) => {
+            Err(ParseError::Incomplete(nom::Needed::new(len - data.len())))
+        }
+        iter::PacketExtent::Complete(_) => parse_with_len(data),
+    }
+}
+
+/// Skip the value at the start of the data, or tell how many more bytes it needs.
+fn skip_value_streaming(data: &[u8]) -> Result<&[u8], ParseError> {
+    let incomplete = |e: nom::Err<Error<&[u8]>>| match e {
+        nom::Err::Incomplete(needed) => ParseError::Incomplete(needed),
+        // streamingのu32とtakeは足りないときにしか失敗しない
+        _ => ParseError::BadLength,
+    };
+
+    let (data, length) = nom::number::streaming::u32::<_, Error<_>>(Endian::Little.into())(data)
+        .map_err(incomplete)?;
+    let (data, _) =
+        nom::bytes::streaming::take::<_, _, Error<_>>(4usize)(data).map_err(incomplete)?;
+    let (rem, _) =
+        nom::bytes::streaming::take::<_, _, Error<_>>(length)(data).map_err(incomplete)?;
+    Ok(rem)
+}
+
+/// Parse the streamed data which must be exactly one packet.
+///
+/// Returns [`ParseError::TrailingBytes`] if bytes are left after the packet, which is a sign
+/// that the packets are not framed correctly.
+pub fn parse_exact(data: &[u8]) -> Result<SkeletonOrFrame, ParseError> {
+    parse_with_options(data, &ParseOptions::new().exact(true))
+}
+
+/// Parse the streamed data without checking that the format is [`EXPECTED_FORMAT`].
+///
+/// Packets of an unknown [`Version`] are parsed as [`Version::LATEST`].
+pub fn parse_lenient(data: &[u8]) -> Result<SkeletonOrFrame, ParseError> {
+    parse_with_options(data, &ParseOptions::new().lenient_format(true))
+}
+
+/// Read the frame number and time of a frame packet without parsing its bones.
+///
+/// Returns [`ParseError::UnexpectedTag`] if the packet is not a frame packet. The format and
+/// the version are checked like in [`parse`].
+pub fn peek_frame_header(data: &[u8]) -> Result<(u32, u32), ParseError> {
+    let opts = &ParseOptions::default();
+    let (_, frame) = frame_section(data, opts)?;
+    let (num, time, _) = parse_frame_header(frame.data, frame.data_offset(), opts)?;
+
+    Ok((num, time))
+}
+
+/// Parse a frame packet into `bones`, which is cleared and refilled instead of allocating a
+/// new buffer for each frame.
+///
+/// Returns [`ParseError::UnexpectedTag`] if the packet is not a frame packet. The sections
+/// after `fram` are not read, and the frame may have at most [`DEFAULT_MAX_BONES`] bones. On
+/// error, `bones` holds the bones parsed before the error.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::net::UdpSocket;
+///
+/// let socket = UdpSocket::bind("0.0.0.0:12351").unwrap();
+/// let mut buf = [0; 1024];
+/// let mut bones = Vec::new();
+///
+/// loop {
+///     let len = socket.recv(&mut buf).unwrap();
+///     if let Ok(frame) = mocopi_parser::parse_into(&buf[..len], &mut bones) {
+///         dbg!(frame.num, frame.bones.len());
+///     }
+/// }
+/// ```
+pub fn parse_into<'b>(
+    data: &[u8],
+    bones: &'b mut Vec<BoneTrans>,
+) -> Result<FrameView<'b>, ParseError> {
+    let opts = &ParseOptions::default();
+    bones.clear();
+
+    let (version, frame) = frame_section(data, opts)?;
+    let (num, time) = match version {
+        Version::V1 => {
+            let (num, time, rem) = parse_frame_header(frame.data, frame.data_offset(), opts)?;
+            parse_bone_trans_into(rem, frame.rem_offset() - rem.len(), opts, bones)?;
+            (num, time)
+        }
+    };
+
+    Ok(FrameView { num, time, bones })
+}
+
+/// Check the head of a frame packet without allocating, and return its version and `fram`.
+fn frame_section<'a>(
+    data: &'a [u8],
+    opts: &ParseOptions,
+) -> Result<(Version, Data<'a>), ParseError> {
+    // parse_headはformatをStringにするので、ここでは中身を直接比べる
+    let head = parse_tagged(data, 0, tags::HEAD, opts)?;
+    let ftyp = parse_tagged(head.data, head.data_offset(), tags::FTYP, opts)?;
+    if ftyp.data != EXPECTED_FORMAT.as_bytes() {
+        return Err(ParseError::UnknownFormat(
+            String::from_utf8_lossy(ftyp.data).into_owned(),
+        ));
+    }
+    let vrsn = parse_tagged(ftyp.rem, ftyp.rem_offset(), tags::VRSN, opts)?;
+    let version = Version::try_from(parse_version(vrsn.data)?.0)?;
+
+    let info = parse_tagged(head.rem, head.rem_offset(), tags::SNDF, opts)?;
+    let frame = parse_tagged(info.rem, info.rem_offset(), tags::FRAM, opts)?;
+    Ok((version, frame))
+}
+
+/// Tell whether the packet is a skeleton or a frame from the name of the section after the info.
+///
+/// The data may end right after that name, since the section itself is not read.
+pub fn packet_kind(data: &[u8]) -> Result<PacketKind, ParseError> {
+    let opts = &ParseOptions::default();
+    let head = parse_tagged(data, 0, tags::HEAD, opts)?;
+    let info = parse_tagged(head.rem, head.rem_offset(), tags::SNDF, opts)?;
+
+    // lengthの後ろのnameだけを見る
+    let name = info.rem.get(4..8).ok_or(ParseError::UnexpectedEof {
+        offset: info.rem_offset(),
+        expected: 8,
+        found: info.rem.len(),
+    })?;
+    if name == tags::SKDF.as_bytes() {
+        Ok(PacketKind::Skeleton)
+    } else if name == tags::FRAM.as_bytes() {
+        Ok(PacketKind::Frame)
+    } else {
+        Err(ParseError::UnknownPacketType(
+            String::from_utf8_lossy(name).into_owned(),
+        ))
+    }
+}
+
+/// Parse a packet whose numbers are in the given byte order.
+pub fn parse_with_endian(data: &[u8], endian: Endian) -> Result<SkeletonOrFrame, ParseError> {
+    parse_with_options(data, &ParseOptions::new().endian(endian))
+}
+
+/// Parse a packet whose skeleton or frame may have at most `max_bones` bones.
+///
+/// The other functions use [`DEFAULT_MAX_BONES`]. A lower limit protects a service which
+/// receives untrusted data from packets which declare huge sections, and returns
+/// [`ParseError::TooManyBones`] as soon as the limit is passed.
+pub fn parse_with_max_bones(data: &[u8], max_bones: usize) -> Result<SkeletonOrFrame, ParseError> {
+    parse_with_options(data, &ParseOptions::new().max_bones(max_bones))
+}
+
+/// Parse a packet with the given options.
+///
+/// [`parse`] is the same as this with [`ParseOptions::default`], and the other `parse_*`
+/// functions are shortcuts for setting one of the options.
+pub fn parse_with_options(data: &[u8], opts: &ParseOptions) -> Result<SkeletonOrFrame, ParseError> {
+    let (len, packet) = parse_packet(data, opts)?;
+    if opts.exact && len != data.len() {
+        return Err(ParseError::TrailingBytes(data.len() - len));
+    }
+    Ok(packet)
+}
+
+/// Parse a packet held in [`Bytes`](bytes::Bytes).
+///
+/// The returned packet owns all its data, so it does not borrow `b` and can outlive it.
+#[cfg(feature = "bytes")]
+pub fn parse_bytes(b: &bytes::Bytes) -> Result<SkeletonOrFrame, ParseError> {
+    parse(b)
+}
+
+fn parse_packet(data: &[u8], opts: &ParseOptions) -> Result<(usize, SkeletonOrFrame), ParseError> {
+    if data.len() < MIN_PACKET_LEN {
+        return Err(ParseError::UnexpectedEof {
+            offset: 0,
+            expected: MIN_PACKET_LEN,
+            found: data.len(),
+        });
+    }
+
+    let (len, head) = parse_head(data, 0, opts)?;
+    if !opts.lenient_format && head.format != EXPECTED_FORMAT {
+        return Err(ParseError::UnknownFormat(head.format));
+    }
+    let version = match head.version() {
+        Ok(version) => version,
+        Err(_) if opts.lenient_format => Version::LATEST,
+        Err(e) => return Err(e),
+    };
+    let mut remain = skip_section(data, 0, len)?;
+    let mut consumed = data.len() - remain.len();
+
+    let (len, info) = parse_info(remain, consumed, opts)?;
+    remain = skip_section(remain, consumed, len)?;
+    consumed = data.len() - remain.len();
+
+    // infoの次のsectionのnameでskdfかframかを判別する
+    let name = parse_value_with(remain, consumed, opts)?.name;
+
+    if tag_matches(name, tags::SKDF, opts) {
+        // versionごとにlayoutが変わったらここで分ける
+        let (len, skeleton) = match version {
+            Version::V1 => parse_skeleton(remain, consumed, opts)?,
+        };
+        remain = skip_section(remain, consumed, len)?;
+        consumed = data.len() - remain.len();
+        let (len, extra) = parse_extra(remain, consumed, opts);
+        consumed += len;
+        Ok((
+            consumed,
+            SkeletonOrFrame::Skeleton(SkeletonPacket {
+                head,
+                info,
+                skeleton,
+                extra,
+            }),
+        ))
+    } else if tag_matches(name, tags::FRAM, opts) {
+        let (len, frame) = match version {
+            Version::V1 => parse_frame(remain, consumed, opts)?,
+        };
+        remain = skip_section(remain, consumed, len)?;
+        consumed = data.len() - remain.len();
+        let (len, extra) = parse_extra(remain, consumed, opts);
+        consumed += len;
+        Ok((
+            consumed,
+            SkeletonOrFrame::Frame(FramePacket {
+                head,
+                info,
+                frame,
+                extra,
+            }),
+        ))
     } else {
-        let (_, frame) = parse_frame(remain)?;
-        Ok(SkeletonOrFrame::Frame(FramePacket { head, info, frame }))
+        Err(ParseError::UnknownPacketType(name.to_string()))
     }
 }
 
+/// Skip the section at the start of `data` whose value has `len` bytes.
+///
+/// `offset` is where the section starts, counted from the start of the packet.
+fn skip_section(data: &[u8], offset: usize, len: u32) -> Result<&[u8], ParseError> {
+    (len as usize)
+        .checked_add(8)
+        .and_then(|size| data.get(size..))
+        .ok_or(ParseError::Truncated {
+            offset,
+            needed: len as usize,
+            available: data.len().saturating_sub(8),
+        })
+}
+
+/// Parse the sections after `skdf` or `fram` which belong to the same packet.
+///
+/// They end at the `head` of the next packet, or at data which is not a section, e.g. the
+/// zeros after a datagram in a receive buffer. Returns their total length and the sections.
+fn parse_extra(data: &[u8], offset: usize, opts: &ParseOptions) -> (usize, Vec<(String, Vec<u8>)>) {
+    let mut extra = Vec::new();
+    let mut remain = data;
+    let mut offset = offset;
+    while let Ok(value) = parse_value_with(remain, offset, opts) {
+        if !is_extra_section(value.name, opts) {
+            break;
+        }
+        extra.push((value.name.to_string(), value.data.to_vec()));
+        remain = value.rem;
+        offset = value.rem_offset();
+    }
+    (data.len() - remain.len(), extra)
+}
+
+/// Whether a value after `skdf` or `fram` named `name` is a section of the same packet, and
+/// not the next packet or data which is not a section.
+fn is_extra_section(name: &str, opts: &ParseOptions) -> bool {
+    !name.is_empty()
+        && name.bytes().all(|b| b.is_ascii_alphanumeric())
+        && !tag_matches(name, tags::HEAD, opts)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
+
+    fn value(name: &str, data: &[u8]) -> Vec<u8> {
+        let mut v = (data.len() as u32).to_le_bytes().to_vec();
+        v.extend_from_slice(name.as_bytes());
+        v.extend_from_slice(data);
+        v
+    }
+
+    fn tran() -> Vec<u8> {
+        let values: [f32; 7] = [0.0, 0.0, 0.0, 1.0, 0.1, 0.2, 0.3];
+        let data: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        value("tran", &data)
+    }
+
+    fn head_and_info() -> Vec<u8> {
+        head_and_info_with_format(b"sony motion format")
+    }
+
+    fn head_and_info_with_format(format: &[u8]) -> Vec<u8> {
+        let head = [value("ftyp", format), value("vrsn", &[0x01])].concat();
+        let info = [
+            value("ipad", &[0xc0, 0xa8, 0x0a, 0x02, 0x00, 0x00, 0x00, 0x00]),
+            value("rcvp", &12351u16.to_le_bytes()),
+        ]
+        .concat();
+        [value("head", &head), value("sndf", &info)].concat()
+    }
+
+    fn bndt(id: u16, parent: u16) -> Vec<u8> {
+        let data = [
+            value("bnid", &id.to_le_bytes()),
+            value("pbid", &parent.to_le_bytes()),
+            tran(),
+        ]
+        .concat();
+        value("bndt", &data)
+    }
+
+    fn skeleton_packet() -> Vec<u8> {
+        let bons = value("bons", &[bndt(0, 0xffff), bndt(1, 0)].concat());
+        [head_and_info(), value("skdf", &bons)].concat()
+    }
+
+    fn frame_packet() -> Vec<u8> {
+        let btdt = |id: u16| {
+            let data = [value("bnid", &id.to_le_bytes()), tran()].concat();
+            value("btdt", &data)
+        };
+        let fram = [
+            value("fnum", &42u32.to_le_bytes()),
+            value("time", &1000u32.to_le_bytes()),
+            value("btrs", &[btdt(0), btdt(1)].concat()),
+        ]
+        .concat();
+        [head_and_info(), value("fram", &fram)].concat()
+    }
 
     #[test]
     fn test_parse_value() {
@@ -342,6 +1173,39 @@ mod tests {
     }
 
     #[test]
+    fn test_parse_value_truncated() {
+        let raw = [
+            0x08, 0x00, 0x00, 0x00,
+            0x62, 0x6e, 0x64, 0x74,
+            0x02, 0x00, 0x00, 0x00
+        ];
+
+        let err = parse_value(&raw).unwrap_err();
+
+        assert_eq!(
+            err,
+            ParseError::Truncated {
+                offset: 0,
+                needed: 8,
+                available: 4
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_value_invalid_name() {
+        let raw = [
+            0x00, 0x00, 0x00, 0x00,
+            0x62, 0xff, 0x64, 0x74
+        ];
+
+        let err = parse_value(&raw).unwrap_err();
+
+        assert!(matches!(err, ParseError::InvalidUtf8(_)));
+    }
+
+    #[test]
+    #[allow(clippy::excessive_precision)]
     fn test_parse_trans() {
         let raw = [
             0x1c, 0x00, 0x00, 0x00,
@@ -371,4 +1235,751 @@ mod tests {
         assert_eq!(data.pos.y, -0.101700753);
         assert_eq!(data.pos.z, 0.128570735);
     }
+
+    #[test]
+    fn test_parse_skeleton_packet() {
+        let raw = skeleton_packet();
+
+        let packet = parse(&raw).unwrap();
+
+        match packet {
+            SkeletonOrFrame::Skeleton(packet) => {
+                assert_eq!(packet.head.format, "sony motion format");
+                assert_eq!(packet.info.port, 12351);
+                assert_eq!(packet.skeleton.bones.len(), 2);
+            }
+            SkeletonOrFrame::Frame(_) => panic!("expected a skeleton packet"),
+        }
+    }
+
+    #[test]
+    fn test_parse_frame_packet() {
+        let raw = frame_packet();
+
+        let packet = parse(&raw).unwrap();
+
+        match packet {
+            SkeletonOrFrame::Frame(packet) => {
+                assert_eq!(packet.frame.num, 42);
+                assert_eq!(packet.frame.time, 1000);
+                assert_eq!(packet.frame.bones.len(), 2);
+            }
+            SkeletonOrFrame::Skeleton(_) => panic!("expected a frame packet"),
+        }
+    }
+
+    #[test]
+    fn test_parse_unknown_packet() {
+        let raw = [head_and_info(), value("xxxx", &[])].concat();
+
+        let err = parse(&raw).err().unwrap();
+
+        assert_eq!(err, ParseError::UnknownPacketType("xxxx".to_string()));
+    }
+
+    #[test]
+    fn test_parse_trans_short() {
+        let raw = value("tran", &[0x00; 20]);
+
+        let err = parse_trans(&raw).unwrap_err();
+
+        assert_eq!(err, ParseError::BadLength);
+    }
+
+    #[test]
+    fn test_parse_bones_corrupted_len() {
+        // bonsの長さがbndtの境界と一致しない
+        let raw = value("bons", &[bndt(0, 0xffff), vec![0x00; 6]].concat());
+
+        let err = parse_bones(&raw, 0, &ParseOptions::default()).unwrap_err();
+
+        assert_eq!(
+            err,
+            ParseError::UnexpectedEof {
+                offset: 8 + bndt(0, 0xffff).len(),
+                expected: 4,
+                found: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_with_len() {
+        let raw = [frame_packet(), skeleton_packet()].concat();
+
+        let (len, first) = parse_with_len(&raw).unwrap();
+        assert_eq!(len, frame_packet().len());
+        assert!(matches!(first, SkeletonOrFrame::Frame(_)));
+
+        let (len, second) = parse_with_len(&raw[len..]).unwrap();
+        assert_eq!(len, skeleton_packet().len());
+        assert!(matches!(second, SkeletonOrFrame::Skeleton(_)));
+    }
+
+    #[test]
+    fn test_parse_streaming() {
+        let raw = frame_packet();
+        let (len, packet) = parse_streaming(&raw).unwrap();
+        assert_eq!(len, raw.len());
+        assert_eq!(packet, parse(&raw).unwrap());
+
+        // tranの途中で切れている
+        let pos = raw.windows(4).position(|w| w == b"tran").unwrap();
+        let cut = pos + 10;
+        assert_eq!(
+            parse_streaming(&raw[..cut]).unwrap_err(),
+            ParseError::Incomplete(nom::Needed::new(raw.len() - cut))
+        );
+        assert!(matches!(
+            parse(&raw[..cut]).unwrap_err(),
+            ParseError::Truncated { .. }
+        ));
+
+        // lengthの途中で切れている
+        assert_eq!(
+            parse_streaming(&raw[..1]).unwrap_err(),
+            ParseError::Incomplete(nom::Needed::new(3))
+        );
+        assert_eq!(
+            parse_streaming(&[]).unwrap_err(),
+            ParseError::Incomplete(nom::Needed::new(4))
+        );
+
+        // 後に続くsectionの途中で切れている
+        let extra = [0x03, 0x00, 0x00, 0x00, b'x', b't', b'r', b'a', 0x01, 0x02, 0x03];
+        let with_extra = [raw.clone(), extra.to_vec()].concat();
+        assert_eq!(
+            parse_streaming(&with_extra[..raw.len() + 9]).unwrap_err(),
+            ParseError::Incomplete(nom::Needed::new(2))
+        );
+        assert_eq!(
+            parse_streaming(&with_extra[..raw.len() + 2]).unwrap_err(),
+            ParseError::Incomplete(nom::Needed::new(6))
+        );
+        let (len, packet) = parse_streaming(&with_extra).unwrap();
+        assert_eq!(len, with_extra.len());
+        assert_eq!(
+            packet.as_frame().unwrap().extra,
+            [("xtra".to_string(), vec![0x01, 0x02, 0x03])]
+        );
+
+        // 揃っていても壊れていればそのerrorを返す
+        let mut raw = raw;
+        raw[pos..pos + 4].copy_from_slice(b"xxxx");
+        assert!(matches!(
+            parse_streaming(&raw).unwrap_err(),
+            ParseError::UnexpectedTag { .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_exact() {
+        assert!(parse_exact(&frame_packet()).unwrap().is_frame());
+
+        let raw = [frame_packet(), vec![0xde, 0xad, 0xbe, 0xef]].concat();
+        assert!(parse(&raw).is_ok());
+        assert_eq!(parse_exact(&raw).unwrap_err(), ParseError::TrailingBytes(4));
+    }
+
+    #[test]
+    fn test_parse_unknown_format() {
+        let btrs = value(
+            "btrs",
+            &value("btdt", &[value("bnid", &[0x00, 0x00]), tran()].concat()),
+        );
+        let fram = [
+            value("fnum", &1u32.to_le_bytes()),
+            value("time", &0u32.to_le_bytes()),
+            btrs,
+        ]
+        .concat();
+        let raw = [head_and_info_with_format(b"other"), value("fram", &fram)].concat();
+
+        let err = parse(&raw).err().unwrap();
+        assert_eq!(err, ParseError::UnknownFormat("other".to_string()));
+        assert_eq!(peek_frame_header(&raw).unwrap_err(), err);
+
+        let packet = parse_lenient(&raw).unwrap();
+        match packet {
+            SkeletonOrFrame::Frame(packet) => assert_eq!(packet.head.format, "other"),
+            SkeletonOrFrame::Skeleton(_) => panic!("expected a frame packet"),
+        }
+    }
+
+    #[test]
+    fn test_skeleton_or_frame_accessors() {
+        let skeleton = parse(&skeleton_packet()).unwrap();
+        assert!(skeleton.is_skeleton());
+        assert!(!skeleton.is_frame());
+        assert_eq!(skeleton.as_skeleton().unwrap().skeleton.bones.len(), 2);
+        assert!(skeleton.as_frame().is_none());
+
+        let frame = parse(&frame_packet()).unwrap();
+        assert!(frame.is_frame());
+        assert!(!frame.is_skeleton());
+        assert_eq!(frame.as_frame().unwrap().frame.num, 42);
+        assert!(frame.as_skeleton().is_none());
+    }
+
+    #[test]
+    fn test_skeleton_or_frame_conversions() {
+        let skeleton = parse(&skeleton_packet()).unwrap();
+        let frame = parse(&frame_packet()).unwrap();
+
+        let packet = SkeletonPacket::try_from(skeleton.clone()).unwrap();
+        assert_eq!(SkeletonOrFrame::from(packet), skeleton);
+        assert_eq!(FramePacket::try_from(skeleton.clone()), Err(skeleton));
+
+        let packet = FramePacket::try_from(frame.clone()).unwrap();
+        assert_eq!(SkeletonOrFrame::from(packet), frame);
+        assert_eq!(SkeletonPacket::try_from(frame.clone()), Err(frame));
+    }
+
+    #[test]
+    fn test_parse_unexpected_tag() {
+        let btdt = value("btdt", &[value("xxxx", &[0x00, 0x00]), tran()].concat());
+        let raw = value("btrs", &btdt);
+
+        let err = parse_bone_trans(&raw, 0, &ParseOptions::default()).unwrap_err();
+
+        assert_eq!(
+            err,
+            ParseError::UnexpectedTag {
+                offset: 16,
+                expected: "bnid",
+                found: "xxxx".to_string()
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_head_info_hash() {
+        use std::collections::HashSet;
+
+        let packets = [frame_packet(), skeleton_packet(), frame_packet()];
+        let keys: HashSet<(Head, Info)> = packets
+            .iter()
+            .map(|raw| match parse(raw).unwrap() {
+                SkeletonOrFrame::Skeleton(packet) => (packet.head, packet.info),
+                SkeletonOrFrame::Frame(packet) => (packet.head, packet.info),
+            })
+            .collect();
+
+        assert_eq!(keys.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_len_past_end() {
+        let mut raw = frame_packet();
+        raw[..4].copy_from_slice(&0xffff_fffcu32.to_le_bytes());
+        assert!(matches!(
+            parse(&raw).unwrap_err(),
+            ParseError::Truncated { offset: 0, .. }
+        ));
+
+        // sndfの長さがpacketの終わりを越えている
+        let mut raw = frame_packet();
+        let pos = raw.windows(4).position(|w| w == b"sndf").unwrap() - 4;
+        let len = raw.len() as u32;
+        raw[pos..pos + 4].copy_from_slice(&len.to_le_bytes());
+        assert!(matches!(
+            parse(&raw).unwrap_err(),
+            ParseError::Truncated { offset, .. } if offset == pos
+        ));
+
+        assert!(matches!(
+            skip_section(&raw, 3, u32::MAX),
+            Err(ParseError::Truncated { offset: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_bones_tran() {
+        // tranはbndtの先頭ではなくpbidの後ろから読む
+        let raw = value("bons", &bndt(3, 2));
+        let (_, bones) = parse_bones(&raw, 0, &ParseOptions::default()).unwrap();
+
+        assert_eq!(
+            bones,
+            [Bone {
+                id: 3,
+                parent: 2,
+                trans: Transform {
+                    rot: Rotation::identity(),
+                    pos: Position {
+                        x: 0.1,
+                        y: 0.2,
+                        z: 0.3
+                    },
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_bones_trailing_values() {
+        // tranの後ろに知らない値があっても次のboneの位置がずれない
+        let bndt = |id: u16, parent: u16| {
+            let data = [
+                value("bnid", &id.to_le_bytes()),
+                value("pbid", &parent.to_le_bytes()),
+                tran(),
+                value("cnfd", &[0x01, 0x00]),
+            ]
+            .concat();
+            value("bndt", &data)
+        };
+        let raw = value("bons", &[bndt(0, 0xffff), bndt(1, 0)].concat());
+        let (_, bones) = parse_bones(&raw, 0, &ParseOptions::default()).unwrap();
+        let ids: Vec<(BoneId, BoneId)> = bones.iter().map(|b| (b.id, b.parent)).collect();
+        assert_eq!(ids, [(0, 0xffff), (1, 0)]);
+
+        let btdt = |id: u16| {
+            let data = [value("bnid", &id.to_le_bytes()), tran(), vec![0x00; 3]].concat();
+            value("btdt", &data)
+        };
+        let raw = value("btrs", &[btdt(0), btdt(1)].concat());
+        let (_, bones) = parse_bone_trans(&raw, 0, &ParseOptions::default()).unwrap();
+        let ids: Vec<BoneId> = bones.iter().map(|b| b.id).collect();
+        assert_eq!(ids, [0, 1]);
+        assert_eq!(bones[1].trans.pos.z, 0.3);
+    }
+
+    #[test]
+    fn test_parse_zero_bones() {
+        let raw = [head_and_info(), value("skdf", &value("bons", &[]))].concat();
+        let packet = parse(&raw).unwrap();
+        assert!(packet.as_skeleton().unwrap().skeleton.bones.is_empty());
+
+        let fram = [
+            value("fnum", &42u32.to_le_bytes()),
+            value("time", &1000u32.to_le_bytes()),
+            value("btrs", &[]),
+        ]
+        .concat();
+        let raw = [head_and_info(), value("fram", &fram)].concat();
+        let packet = parse(&raw).unwrap();
+        assert!(packet.as_frame().unwrap().frame.bones.is_empty());
+    }
+
+    #[test]
+    fn test_parse_error_offset() {
+        // 最初のbtdtにあるbnidの名前を壊す
+        let mut raw = frame_packet();
+        let pos = raw.windows(4).position(|w| w == b"bnid").unwrap();
+        raw[pos..pos + 4].copy_from_slice(b"xxxx");
+
+        assert_eq!(
+            parse(&raw).unwrap_err(),
+            ParseError::UnexpectedTag {
+                offset: pos - 4,
+                expected: "bnid",
+                found: "xxxx".to_string()
+            }
+        );
+
+        // framの途中で切れている
+        let len = head_and_info().len();
+        let err = parse(&frame_packet()[..len + 16]).unwrap_err();
+        assert!(matches!(err, ParseError::Truncated { offset, .. } if offset == len));
+    }
+
+    #[test]
+    fn test_parse_too_many_bones() {
+        let bons = value("bons", &[bndt(0, 0xffff), bndt(1, 0), bndt(2, 1)].concat());
+        let raw = [head_and_info(), value("skdf", &bons)].concat();
+        assert!(parse_with_max_bones(&raw, 3).is_ok());
+        assert_eq!(
+            parse_with_max_bones(&raw, 2).unwrap_err(),
+            ParseError::TooManyBones { max: 2 }
+        );
+        assert_eq!(
+            parse_with_max_bones(&frame_packet(), 1).unwrap_err(),
+            ParseError::TooManyBones { max: 1 }
+        );
+
+        let bndts: Vec<u8> = (0..=DEFAULT_MAX_BONES as u16)
+            .flat_map(|id| bndt(id, 0xffff))
+            .collect();
+        let raw = [head_and_info(), value("skdf", &value("bons", &bndts))].concat();
+        assert_eq!(
+            parse(&raw).unwrap_err(),
+            ParseError::TooManyBones {
+                max: DEFAULT_MAX_BONES
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_lenient_names() {
+        let lenient = ParseOptions::new().lenient_names(true);
+        let expected = parse(&frame_packet()).unwrap();
+
+        // tranのnameの最後の1byteを壊す
+        for broken in [b'\0', 0xe9] {
+            let mut raw = frame_packet();
+            let pos = raw.windows(4).position(|w| w == b"tran").unwrap();
+            raw[pos + 3] = broken;
+
+            assert_eq!(parse_with_options(&raw, &lenient).unwrap(), expected);
+            assert!(parse(&raw).is_err());
+        }
+
+        let mut raw = frame_packet();
+        let pos = raw.windows(4).position(|w| w == b"tran").unwrap();
+        raw[pos + 3] = b'\0';
+        assert_eq!(
+            parse(&raw).unwrap_err(),
+            ParseError::UnexpectedTag {
+                offset: pos - 4,
+                expected: "tran",
+                found: "tra\0".to_string()
+            }
+        );
+
+        // 何も読めないnameはどのnameにも一致しない
+        raw[pos..pos + 4].copy_from_slice(b"\0\0\0\0");
+        assert!(parse_with_options(&raw, &lenient).is_err());
+
+        // 短すぎるnameはtranにもtimeにも一致しない
+        raw[pos..pos + 4].copy_from_slice(b"t\0\0\0");
+        assert!(parse_with_options(&raw, &lenient).is_err());
+
+        // packetの種類を表すnameも壊れていてよい
+        let mut raw = frame_packet();
+        let pos = raw.windows(4).position(|w| w == b"fram").unwrap();
+        raw[pos + 3] = b'\0';
+        assert_eq!(parse_with_options(&raw, &lenient).unwrap(), expected);
+        assert_eq!(
+            parse(&raw).unwrap_err(),
+            ParseError::UnknownPacketType("fra\0".to_string())
+        );
+
+        // 次のpacketのheadが壊れていても、後に続くsectionとは見なさない
+        let first = frame_packet();
+        let mut raw = [first.clone(), frame_packet()].concat();
+        raw[first.len() + 7] = b'\0';
+        let (len, _) = parse_packet(&raw, &lenient).unwrap();
+        assert_eq!(len, first.len());
+    }
+
+    #[test]
+    fn test_parse_with_options() {
+        assert_eq!(
+            parse_with_options(&frame_packet(), &ParseOptions::default()).unwrap(),
+            parse(&frame_packet()).unwrap()
+        );
+
+        // big-endianで、後ろに余計なbyteがある
+        let mut raw = to_big_endian(&frame_packet());
+        raw.extend_from_slice(&[0x00; 4]);
+        let opts = ParseOptions::new().endian(Endian::Big);
+        assert!(parse_with_options(&raw, &opts).is_ok());
+        assert_eq!(
+            parse_with_options(&raw, &opts.exact(true)).unwrap_err(),
+            ParseError::TrailingBytes(4)
+        );
+        assert_eq!(
+            parse_with_options(&raw, &opts.max_bones(1)).unwrap_err(),
+            ParseError::TooManyBones { max: 1 }
+        );
+
+        // formatが違い、nameも壊れている
+        let mut raw = [
+            head_and_info_with_format(b"other format"),
+            value("skdf", &value("bons", &[])),
+        ]
+        .concat();
+        raw[4..8].copy_from_slice(b"hea\0");
+        let opts = ParseOptions::new().lenient_format(true);
+        assert!(parse_with_options(&raw, &opts).is_err());
+        let packet = parse_with_options(&raw, &opts.lenient_names(true)).unwrap();
+        assert_eq!(packet.as_skeleton().unwrap().head.format, "other format");
+    }
+
+    #[test]
+    fn test_packet_kind() {
+        let len = head_and_info().len() + 8;
+        assert_eq!(
+            packet_kind(&skeleton_packet()[..len]).unwrap(),
+            PacketKind::Skeleton
+        );
+        assert_eq!(
+            packet_kind(&frame_packet()[..len]).unwrap(),
+            PacketKind::Frame
+        );
+
+        assert!(matches!(
+            packet_kind(&frame_packet()[..len - 1]).unwrap_err(),
+            ParseError::UnexpectedEof { .. }
+        ));
+        let raw = [head_and_info(), value("xxxx", &[])].concat();
+        assert_eq!(
+            packet_kind(&raw).unwrap_err(),
+            ParseError::UnknownPacketType("xxxx".to_string())
+        );
+    }
+
+    #[test]
+    fn test_peek_frame_header() {
+        assert_eq!(peek_frame_header(&frame_packet()).unwrap(), (42, 1000));
+
+        let err = peek_frame_header(&skeleton_packet()).unwrap_err();
+        assert_eq!(
+            err,
+            ParseError::UnexpectedTag {
+                offset: head_and_info().len(),
+                expected: "fram",
+                found: "skdf".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_into() {
+        let SkeletonOrFrame::Frame(packet) = parse(&frame_packet()).unwrap() else {
+            panic!("not a frame");
+        };
+
+        let mut bones = Vec::with_capacity(8);
+        let ptr = bones.as_ptr();
+        for _ in 0..2 {
+            let frame = parse_into(&frame_packet(), &mut bones).unwrap();
+            assert_eq!(frame.num, 42);
+            assert_eq!(frame.time, 1000);
+            assert_eq!(frame.bones, packet.frame.bones.as_slice());
+        }
+        // 同じbufferを使い回している
+        assert_eq!(bones.len(), 2);
+        assert_eq!(bones.as_ptr(), ptr);
+
+        assert!(matches!(
+            parse_into(&skeleton_packet(), &mut bones).unwrap_err(),
+            ParseError::UnexpectedTag {
+                expected: "fram",
+                ..
+            }
+        ));
+        assert!(bones.is_empty());
+    }
+
+    fn info_with_addr(addr: &[u8]) -> Vec<u8> {
+        let info = [value("ipad", addr), value("rcvp", &12351u16.to_le_bytes())].concat();
+        value("sndf", &info)
+    }
+
+    #[test]
+    fn test_parse_info_addr() {
+        let (_, info) = parse_info(
+            &info_with_addr(&[0xc0, 0xa8, 0x0a, 0x02]),
+            0,
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(info.addr, Ipv4Addr::new(192, 168, 10, 2));
+
+        let (_, info) = parse_info(
+            &info_with_addr(&[0xc0, 0xa8, 0x0a, 0x02, 0x00, 0x00, 0x00, 0x00]),
+            0,
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(info.addr, Ipv4Addr::new(192, 168, 10, 2));
+        assert_eq!(info.port, 12351);
+
+        let (_, info) = parse_info(
+            &info_with_addr(&[
+                0xfe, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x01,
+            ]),
+            0,
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(info.addr, Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1));
+
+        let err = parse_info(&info_with_addr(&[0x00; 6]), 0, &ParseOptions::default()).unwrap_err();
+        assert_eq!(err, ParseError::BadAddrLen(6));
+    }
+
+    #[test]
+    fn test_info_socket_addr() {
+        let (_, info) = parse_info(
+            &info_with_addr(&[0xc0, 0xa8, 0x0a, 0x02, 0x00, 0x00, 0x00, 0x00]),
+            0,
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(info.ip().to_string(), "192.168.10.2");
+        assert_eq!(info.socket_addr().to_string(), "192.168.10.2:12351");
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        for raw in [&[][..], &[0x00], &frame_packet()[..MIN_PACKET_LEN - 1]] {
+            assert_eq!(
+                parse_with_len(raw).unwrap_err(),
+                ParseError::UnexpectedEof {
+                    offset: 0,
+                    expected: MIN_PACKET_LEN,
+                    found: raw.len()
+                }
+            );
+        }
+    }
+
+    /// Convert a little-endian packet into the same packet in big-endian.
+    fn to_big_endian(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut remain = data;
+        while !remain.is_empty() {
+            let value = parse_value(remain).unwrap();
+            let inner = match value.name {
+                "head" | "sndf" | "skdf" | "fram" | "bons" | "btrs" | "bndt" | "btdt" => {
+                    to_big_endian(value.data)
+                }
+                // 数値は各要素のbyte順を反転する
+                "bnid" | "pbid" | "rcvp" => value
+                    .data
+                    .chunks(2)
+                    .flat_map(|c| c.iter().rev())
+                    .copied()
+                    .collect(),
+                "fnum" | "time" | "tran" => value
+                    .data
+                    .chunks(4)
+                    .flat_map(|c| c.iter().rev())
+                    .copied()
+                    .collect(),
+                _ => value.data.to_vec(),
+            };
+            out.extend_from_slice(&value.len.to_be_bytes());
+            out.extend_from_slice(value.name.as_bytes());
+            out.extend_from_slice(&inner);
+            remain = value.rem;
+        }
+        out
+    }
+
+    #[test]
+    fn test_parse_with_endian() {
+        for raw in [frame_packet(), skeleton_packet()] {
+            let little = parse_with_endian(&raw, Endian::Little).unwrap();
+            let big = parse_with_endian(&to_big_endian(&raw), Endian::Big).unwrap();
+
+            assert_eq!(little, parse(&raw).unwrap());
+            assert_eq!(big, little);
+        }
+
+        assert!(parse_with_endian(&frame_packet(), Endian::Big).is_err());
+    }
+
+    #[test]
+    fn test_try_from() {
+        let raw = frame_packet();
+
+        let packet: SkeletonOrFrame = raw.as_slice().try_into().unwrap();
+        assert_eq!(packet, parse(&raw).unwrap());
+
+        let err = SkeletonOrFrame::try_from(&raw[..10]).unwrap_err();
+        assert!(matches!(err, ParseError::UnexpectedEof { .. }));
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_parse_bytes() {
+        fn owned<T: 'static>(t: T) -> T {
+            t
+        }
+
+        let b = bytes::Bytes::from(frame_packet());
+        let packet = owned(parse_bytes(&b).unwrap());
+        drop(b);
+
+        assert!(packet.is_frame());
+    }
+
+    #[test]
+    fn test_parse_extra() {
+        let extra = [value("xtra", &[0x01, 0x02]), value("zzzz", &[])].concat();
+        let raw = [
+            frame_packet(),
+            extra.clone(),
+            skeleton_packet(),
+            vec![0x00; 16],
+        ]
+        .concat();
+
+        let (len, first) = parse_with_len(&raw).unwrap();
+        assert_eq!(len, frame_packet().len() + extra.len());
+        assert_eq!(
+            first.as_frame().unwrap().extra,
+            [
+                ("xtra".to_string(), vec![0x01, 0x02]),
+                ("zzzz".to_string(), vec![])
+            ]
+        );
+
+        // 後ろの0埋めはsectionとして扱わない
+        let (len, second) = parse_with_len(&raw[len..]).unwrap();
+        assert_eq!(len, skeleton_packet().len());
+        assert!(second.as_skeleton().unwrap().extra.is_empty());
+    }
+
+    #[test]
+    fn test_parse_unsupported_version() {
+        // head, ftypの後ろにあるvrsnの値を書き換える
+        let mut raw = frame_packet();
+        let pos = 8 + value("ftyp", EXPECTED_FORMAT.as_bytes()).len() + 8;
+        assert_eq!(raw[pos], 0x01);
+        raw[pos] = 0x02;
+
+        assert_eq!(parse(&raw).unwrap_err(), ParseError::UnsupportedVersion(2));
+        assert_eq!(
+            peek_frame_header(&raw).unwrap_err(),
+            ParseError::UnsupportedVersion(2)
+        );
+        assert!(parse_lenient(&raw).unwrap().is_frame());
+    }
+
+    #[test]
+    fn test_parse_version_number() {
+        let packet = parse(&frame_packet()).unwrap();
+        let head = &packet.as_frame().unwrap().head;
+        assert_eq!(head.ver, 1);
+        assert_eq!(head.ver_minor, None);
+        assert_eq!(head.version_number(), VersionNumber { major: 1, minor: 0 });
+
+        // headだけを作り直す
+        let head = |ver: &[u8]| {
+            let head = [
+                value("ftyp", EXPECTED_FORMAT.as_bytes()),
+                value("vrsn", ver),
+            ]
+            .concat();
+            value("head", &head)
+        };
+        let with_vrsn = |ver: &[u8]| {
+            let raw = frame_packet();
+            [head(ver), raw[head(&[0x01]).len()..].to_vec()].concat()
+        };
+        let raw = with_vrsn(&[0x01, 0x02]);
+        let packet = parse(&raw).unwrap();
+        let head = &packet.as_frame().unwrap().head;
+        assert_eq!(head.ver, 1);
+        assert_eq!(head.ver_minor, Some(2));
+        assert_eq!(head.version_number(), VersionNumber { major: 1, minor: 2 });
+        assert_eq!(parse_into(&raw, &mut Vec::new()).unwrap().num, 42);
+
+        for ver in [&[][..], &[0x01, 0x02, 0x03]] {
+            let raw = with_vrsn(ver);
+            assert_eq!(parse(&raw).unwrap_err(), ParseError::BadLength);
+            assert_eq!(
+                parse_into(&raw, &mut Vec::new()).unwrap_err(),
+                ParseError::BadLength
+            );
+        }
+    }
 }
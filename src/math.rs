@@ -0,0 +1,757 @@
+use crate::float::{acos, asin, atan2, sin, sqrt};
+use crate::{Position, Rotation, TransVal, Transform};
+use core::ops::{Add, Mul, Sub};
+
+impl Rotation {
+    /// The rotation which does nothing.
+    pub const fn identity() -> Rotation {
+        Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        }
+    }
+
+    /// The length of the quaternion.
+    pub fn magnitude(&self) -> TransVal {
+        sqrt(self.dot(self))
+    }
+
+    /// The dot product of the quaternions as 4D vectors.
+    pub fn dot(&self, other: &Rotation) -> TransVal {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    /// Scale the quaternion to unit length.
+    ///
+    /// A zero quaternion cannot be normalized, so the identity is returned instead.
+    pub fn normalize(&self) -> Rotation {
+        let m = self.magnitude();
+        if m == 0.0 {
+            return Rotation::identity();
+        }
+
+        Rotation {
+            x: self.x / m,
+            y: self.y / m,
+            z: self.z / m,
+            w: self.w / m,
+        }
+    }
+
+    /// Convert the quaternion to Euler angles `(roll, pitch, yaw)` in radians.
+    ///
+    /// The angles are in the ZYX order: the rotation is yaw around Z, then pitch around
+    /// the new Y, then roll around the new X. At the poles (pitch = ±90°) the pitch is
+    /// clamped instead of becoming NaN.
+    pub fn to_euler(&self) -> (TransVal, TransVal, TransVal) {
+        let Rotation { x, y, z, w } = *self;
+
+        let roll = atan2(2.0 * (w * x + y * z), 1.0 - 2.0 * (x * x + y * y));
+        let pitch = asin((2.0 * (w * y - z * x)).clamp(-1.0, 1.0));
+        let yaw = atan2(2.0 * (w * z + x * y), 1.0 - 2.0 * (y * y + z * z));
+
+        (roll, pitch, yaw)
+    }
+
+    /// Convert the quaternion to Euler angles `(roll, pitch, yaw)` in degrees.
+    ///
+    /// See [`Rotation::to_euler`] for the rotation order.
+    pub fn to_euler_degrees(&self) -> (TransVal, TransVal, TransVal) {
+        let (roll, pitch, yaw) = self.to_euler();
+        (roll.to_degrees(), pitch.to_degrees(), yaw.to_degrees())
+    }
+
+    /// Spherical linear interpolation from `self` (`t = 0`) to `other` (`t = 1`).
+    ///
+    /// The interpolation takes the shorter arc, so `other` may be negated.
+    pub fn slerp(&self, other: &Rotation, t: f32) -> Rotation {
+        let mut b = *other;
+        let mut dot = self.dot(&b);
+        if dot < 0.0 {
+            b = Rotation {
+                x: -b.x,
+                y: -b.y,
+                z: -b.z,
+                w: -b.w,
+            };
+            dot = -dot;
+        }
+
+        // ほぼ同じ向きのときはsinが0に近くなるので線形補間する
+        let (s0, s1) = if dot > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = acos(dot);
+            let sin_theta = sin(theta);
+            (
+                sin((1.0 - t) * theta) / sin_theta,
+                sin(t * theta) / sin_theta,
+            )
+        };
+
+        Rotation {
+            x: s0 * self.x + s1 * b.x,
+            y: s0 * self.y + s1 * b.y,
+            z: s0 * self.z + s1 * b.z,
+            w: s0 * self.w + s1 * b.w,
+        }
+        .normalize()
+    }
+
+    /// The conjugate of the quaternion, which is its inverse if it has unit length.
+    pub fn conjugate(&self) -> Rotation {
+        Rotation {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+            w: self.w,
+        }
+    }
+
+    /// The same rotation with `w >= 0`, negating every component if `w` is negative.
+    ///
+    /// `q` and `-q` are the same rotation, so this removes the sign flips between frames.
+    pub fn canonicalize(&self) -> Rotation {
+        if self.w < 0.0 {
+            Rotation {
+                x: -self.x,
+                y: -self.y,
+                z: -self.z,
+                w: -self.w,
+            }
+        } else {
+            *self
+        }
+    }
+
+    /// The Hamilton product `self * other`, which applies `other` first and then `self`.
+    pub fn multiply(&self, other: &Rotation) -> Rotation {
+        *self * *other
+    }
+
+    /// Rotate the point `v` by the quaternion, which must have unit length.
+    pub fn rotate(&self, v: Position) -> Position {
+        // t = 2 * (q × v), v' = v + w * t + q × t
+        let tx = 2.0 * (self.y * v.z - self.z * v.y);
+        let ty = 2.0 * (self.z * v.x - self.x * v.z);
+        let tz = 2.0 * (self.x * v.y - self.y * v.x);
+
+        Position {
+            x: v.x + self.w * tx + (self.y * tz - self.z * ty),
+            y: v.y + self.w * ty + (self.z * tx - self.x * tz),
+            z: v.z + self.w * tz + (self.x * ty - self.y * tx),
+        }
+    }
+
+    /// Whether every component differs from `other` by at most `epsilon`.
+    ///
+    /// `q` and `-q` are the same rotation, but they are not approximately equal here.
+    pub fn approx_eq(&self, other: &Rotation, epsilon: TransVal) -> bool {
+        (self.x - other.x).abs() <= epsilon
+            && (self.y - other.y).abs() <= epsilon
+            && (self.z - other.z).abs() <= epsilon
+            && (self.w - other.w).abs() <= epsilon
+    }
+}
+
+impl Default for Rotation {
+    fn default() -> Self {
+        Rotation::identity()
+    }
+}
+
+/// The Hamilton product, which applies `rhs` first and then `self`.
+impl Mul for Rotation {
+    type Output = Rotation;
+
+    fn mul(self, rhs: Rotation) -> Rotation {
+        let (a, b) = (self, rhs);
+        Rotation {
+            x: a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            y: a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            z: a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+            w: a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+        }
+    }
+}
+
+impl Position {
+    /// The origin.
+    pub const fn zero() -> Position {
+        Position {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    }
+
+    /// The dot product of the vectors.
+    pub fn dot(&self, other: &Position) -> TransVal {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// The cross product `self × other`.
+    pub fn cross(&self, other: &Position) -> Position {
+        Position {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
+
+    /// The length of the vector.
+    pub fn length(&self) -> TransVal {
+        sqrt(self.dot(self))
+    }
+
+    /// The distance between the points.
+    ///
+    /// # Examples
+    ///
+    /// Measure the length of a limb from the world-space positions of its bones:
+    ///
+    /// ```
+    /// use mocopi_parser::{BoneName, Position, Rotation, SkeletonBuilder, Transform, ROOT_PARENT};
+    ///
+    /// let offset = |y| Transform {
+    ///     rot: Rotation::identity(),
+    ///     pos: Position { x: 0.0, y, z: 0.0 },
+    /// };
+    /// let packet = SkeletonBuilder::new()
+    ///     .add_bone(BoneName::Root.id(), ROOT_PARENT, offset(1.0))
+    ///     .add_bone(BoneName::LUpLeg.id(), BoneName::Root.id(), offset(-0.1))
+    ///     .add_bone(BoneName::LLowLeg.id(), BoneName::LUpLeg.id(), offset(-0.4))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let world = packet.skeleton.world_transforms().unwrap();
+    /// let thigh = world[1].1.pos.distance(&world[2].1.pos);
+    /// assert!((thigh - 0.4).abs() < 1e-6);
+    /// ```
+    pub fn distance(&self, other: &Position) -> TransVal {
+        (*self - *other).length()
+    }
+
+    /// Scale the vector to unit length.
+    ///
+    /// The zero vector cannot be normalized, so it is returned as is.
+    pub fn normalize(&self) -> Position {
+        let len = self.length();
+        if len == 0.0 {
+            return *self;
+        }
+        *self * (1.0 / len)
+    }
+
+    /// Whether every component differs from `other` by at most `epsilon`.
+    pub fn approx_eq(&self, other: &Position, epsilon: TransVal) -> bool {
+        (self.x - other.x).abs() <= epsilon
+            && (self.y - other.y).abs() <= epsilon
+            && (self.z - other.z).abs() <= epsilon
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position::zero()
+    }
+}
+
+impl Add for Position {
+    type Output = Position;
+
+    fn add(self, rhs: Position) -> Position {
+        Position {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
+impl Sub for Position {
+    type Output = Position;
+
+    fn sub(self, rhs: Position) -> Position {
+        Position {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+        }
+    }
+}
+
+impl Mul<TransVal> for Position {
+    type Output = Position;
+
+    fn mul(self, rhs: TransVal) -> Position {
+        Position {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+        }
+    }
+}
+
+impl Transform {
+    /// The transform which neither rotates nor moves.
+    pub const fn identity() -> Transform {
+        Transform {
+            rot: Rotation::identity(),
+            pos: Position::zero(),
+        }
+    }
+
+    /// Whether both the rotation and the position are approximately equal to `other`'s.
+    pub fn approx_eq(&self, other: &Transform, epsilon: TransVal) -> bool {
+        self.rot.approx_eq(&other.rot, epsilon) && self.pos.approx_eq(&other.pos, epsilon)
+    }
+
+    /// Apply `other` in the space of `self`, e.g. a child transform under its parent.
+    ///
+    /// The rotation must have unit length.
+    pub fn compose(&self, other: &Transform) -> Transform {
+        Transform {
+            rot: self.rot * other.rot,
+            pos: self.pos + self.rot.rotate(other.pos),
+        }
+    }
+
+    /// The transform which undoes `self`, so that composing them in either order gives the
+    /// identity.
+    ///
+    /// The rotation must have unit length.
+    pub fn inverse(&self) -> Transform {
+        let rot = self.rot.conjugate();
+        Transform {
+            rot,
+            pos: rot.rotate(self.pos) * -1.0,
+        }
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Quaternion;
+    use core::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_2};
+
+    fn assert_angles(actual: (f32, f32, f32), expected: (f32, f32, f32)) {
+        assert!((actual.0 - expected.0).abs() < 1e-3, "{:?}", actual);
+        assert!((actual.1 - expected.1).abs() < 1e-3, "{:?}", actual);
+        assert!((actual.2 - expected.2).abs() < 1e-3, "{:?}", actual);
+    }
+
+    #[test]
+    fn test_to_euler() {
+        let identity = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        };
+        assert_angles(identity.to_euler(), (0.0, 0.0, 0.0));
+
+        let x = Rotation {
+            x: FRAC_1_SQRT_2,
+            y: 0.0,
+            z: 0.0,
+            w: FRAC_1_SQRT_2,
+        };
+        assert_angles(x.to_euler(), (FRAC_PI_2, 0.0, 0.0));
+
+        let y = Rotation {
+            x: 0.0,
+            y: FRAC_1_SQRT_2,
+            z: 0.0,
+            w: FRAC_1_SQRT_2,
+        };
+        assert_angles(y.to_euler(), (0.0, FRAC_PI_2, 0.0));
+
+        let z = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: FRAC_1_SQRT_2,
+            w: FRAC_1_SQRT_2,
+        };
+        assert_angles(z.to_euler(), (0.0, 0.0, FRAC_PI_2));
+    }
+
+    #[test]
+    fn test_to_euler_degrees() {
+        let z = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: FRAC_1_SQRT_2,
+            w: FRAC_1_SQRT_2,
+        };
+        let (roll, pitch, yaw) = z.to_euler_degrees();
+
+        assert!(roll.abs() < 1e-4);
+        assert!(pitch.abs() < 1e-4);
+        assert!((yaw - 90.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_normalize() {
+        let r = Rotation {
+            x: 1.0,
+            y: 2.0,
+            z: 2.0,
+            w: 4.0,
+        };
+        assert_eq!(r.magnitude(), 5.0);
+
+        let n = r.normalize();
+        assert!((n.magnitude() - 1.0).abs() < 1e-6);
+        assert_eq!(
+            n,
+            Rotation {
+                x: 0.2,
+                y: 0.4,
+                z: 0.4,
+                w: 0.8
+            }
+        );
+
+        let zero = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 0.0,
+        };
+        assert_eq!(
+            zero.normalize(),
+            Rotation {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                w: 1.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_slerp() {
+        let identity = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        };
+        let z90 = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: FRAC_1_SQRT_2,
+            w: FRAC_1_SQRT_2,
+        };
+
+        assert_eq!(identity.slerp(&z90, 0.0), identity);
+
+        let end = identity.slerp(&z90, 1.0);
+        assert!((end.z - FRAC_1_SQRT_2).abs() < 1e-6);
+        assert!((end.w - FRAC_1_SQRT_2).abs() < 1e-6);
+
+        // 45°回転
+        let half = identity.slerp(&z90, 0.5);
+        assert!((half.z - 0.38268343).abs() < 1e-6);
+        assert!((half.w - 0.9238795).abs() < 1e-6);
+
+        // 符号が反転していても短い方の弧を通る
+        let negated = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: -FRAC_1_SQRT_2,
+            w: -FRAC_1_SQRT_2,
+        };
+        let half = identity.slerp(&negated, 0.5);
+        assert!((half.z - 0.38268343).abs() < 1e-6);
+        assert!((half.w - 0.9238795).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_mul() {
+        let x90 = Rotation {
+            x: FRAC_1_SQRT_2,
+            y: 0.0,
+            z: 0.0,
+            w: FRAC_1_SQRT_2,
+        };
+        let y90 = Rotation {
+            x: 0.0,
+            y: FRAC_1_SQRT_2,
+            z: 0.0,
+            w: FRAC_1_SQRT_2,
+        };
+
+        // 掛ける順番で結果が変わる
+        let xy = x90 * y90;
+        let yx = y90 * x90;
+        assert_ne!(xy, yx);
+        assert!((xy.z - 0.5).abs() < 1e-6);
+        assert!((yx.z + 0.5).abs() < 1e-6);
+
+        let identity = x90 * x90.conjugate();
+        assert!((identity.w - 1.0).abs() < 1e-6);
+        assert!(identity.x.abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_rotate() {
+        let p = Position {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+        };
+        let identity = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        };
+        assert_eq!(identity.rotate(p), p);
+
+        let z90 = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: FRAC_1_SQRT_2,
+            w: FRAC_1_SQRT_2,
+        };
+        let rotated = z90.rotate(p);
+        assert!((rotated.x + 2.0).abs() < 1e-6);
+        assert!((rotated.y - 1.0).abs() < 1e-6);
+        assert!((rotated.z - 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let q = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: -FRAC_1_SQRT_2,
+            w: -FRAC_1_SQRT_2,
+        };
+        let canonical = q.canonicalize();
+        assert_eq!(
+            canonical,
+            Rotation {
+                x: 0.0,
+                y: 0.0,
+                z: FRAC_1_SQRT_2,
+                w: FRAC_1_SQRT_2,
+            }
+        );
+
+        // 符号を反転しても同じ回転になる
+        let p = Position {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+        };
+        assert!(q.rotate(p).approx_eq(&canonical.rotate(p), 1e-6));
+
+        assert_eq!(canonical.canonicalize(), canonical);
+    }
+
+    #[test]
+    fn test_position_ops() {
+        let a = Position {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+        };
+        let b = Position {
+            x: 0.5,
+            y: -1.0,
+            z: 2.0,
+        };
+
+        assert_eq!(
+            a + b,
+            Position {
+                x: 1.5,
+                y: 1.0,
+                z: 5.0
+            }
+        );
+        assert_eq!(
+            a - b,
+            Position {
+                x: 0.5,
+                y: 3.0,
+                z: 1.0
+            }
+        );
+        assert_eq!(
+            a * 2.0,
+            Position {
+                x: 2.0,
+                y: 4.0,
+                z: 6.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_identity() {
+        assert_eq!(Rotation::default(), Rotation::identity());
+        assert_eq!(Position::default(), Position::zero());
+        assert_eq!(Transform::default(), Transform::identity());
+
+        let trans = Transform {
+            rot: Rotation {
+                x: 0.0,
+                y: 0.0,
+                z: FRAC_1_SQRT_2,
+                w: FRAC_1_SQRT_2,
+            },
+            pos: Position {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            },
+        };
+        assert_eq!(Transform::identity().compose(&trans), trans);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let t = Transform {
+            rot: Rotation {
+                x: 0.2,
+                y: -0.4,
+                z: 0.1,
+                w: 0.9,
+            }
+            .normalize(),
+            pos: Position {
+                x: 1.0,
+                y: -2.0,
+                z: 0.5,
+            },
+        };
+        let identity = Transform::identity();
+
+        assert!(t.compose(&t.inverse()).approx_eq(&identity, 1e-6));
+        assert!(t.inverse().compose(&t).approx_eq(&identity, 1e-6));
+        assert!(t.inverse().inverse().approx_eq(&t, 1e-6));
+
+        // 親の逆変換を掛けると子のlocalな変換に戻る
+        let local = Transform {
+            rot: Rotation::identity(),
+            pos: Position {
+                x: 0.0,
+                y: 1.0,
+                z: 0.0,
+            },
+        };
+        let world = t.compose(&local);
+        assert!(t.inverse().compose(&world).approx_eq(&local, 1e-6));
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        let a = Transform {
+            rot: Rotation {
+                x: 0.0,
+                y: 0.0,
+                z: FRAC_1_SQRT_2,
+                w: FRAC_1_SQRT_2,
+            },
+            pos: Position {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            },
+        };
+        let mut b = a;
+        b.rot.w += 1e-4;
+        b.pos.z -= 1e-4;
+
+        assert!(a.approx_eq(&b, 1e-3));
+        assert!(!a.approx_eq(&b, 1e-5));
+        assert!(!a.rot.approx_eq(&b.rot, 1e-5));
+        assert!(a.pos.approx_eq(&b.pos, 1e-3));
+        assert!(!a.pos.approx_eq(&b.pos, 1e-5));
+    }
+
+    #[test]
+    fn test_quaternion_helpers() {
+        let a: Quaternion = Rotation {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+            w: 4.0,
+        };
+        let b = Rotation {
+            x: 0.5,
+            y: -1.0,
+            z: 0.0,
+            w: 2.0,
+        };
+        assert_eq!(a.dot(&b), 0.5 - 2.0 + 0.0 + 8.0);
+
+        // (w, x, y, z) = (4, 1, 2, 3) * (2, 0.5, -1, 0)を手で計算した値
+        assert_eq!(
+            a.multiply(&b),
+            Rotation {
+                x: 7.0,
+                y: 1.5,
+                z: 4.0,
+                w: 9.5,
+            }
+        );
+        assert_eq!(
+            a.multiply(&a.conjugate()),
+            Rotation {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                w: 30.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_vector_helpers() {
+        let a = Position {
+            x: 1.0,
+            y: 2.0,
+            z: 2.0,
+        };
+        let b = Position {
+            x: 0.0,
+            y: 1.0,
+            z: 0.0,
+        };
+
+        assert_eq!(a.dot(&b), 2.0);
+        assert_eq!(
+            a.cross(&b),
+            Position {
+                x: -2.0,
+                y: 0.0,
+                z: 1.0
+            }
+        );
+        assert_eq!(a.length(), 3.0);
+        assert_eq!(a.distance(&b), sqrt(6.0));
+        assert!(a.normalize().approx_eq(
+            &Position {
+                x: 1.0 / 3.0,
+                y: 2.0 / 3.0,
+                z: 2.0 / 3.0
+            },
+            1e-6
+        ));
+        assert_eq!(Position::zero().normalize(), Position::zero());
+    }
+}
@@ -0,0 +1,143 @@
+//! Mirror frames from left to right.
+
+use crate::{BoneId, BoneTrans, Frame, Position, Rotation, Skeleton, Transform};
+
+/// Mirror the frame across the YZ plane, swapping the transforms of the paired bones.
+///
+/// The positions have X negated and the rotations have Y and Z negated, which is the same
+/// rotation seen in a mirror. Each pair in `left_right_pairs` is a left bone and its right
+/// counterpart, which depend on the rig. If only one bone of a pair is in the frame, the
+/// other one takes its transform from `skeleton`. The bones keep their order.
+pub fn mirror_frame(
+    frame: &Frame,
+    skeleton: &Skeleton,
+    left_right_pairs: &[(BoneId, BoneId)],
+) -> Frame {
+    let counterpart = |id: BoneId| {
+        left_right_pairs.iter().find_map(|&(left, right)| {
+            if id == left {
+                Some(right)
+            } else if id == right {
+                Some(left)
+            } else {
+                None
+            }
+        })
+    };
+
+    let bones = frame
+        .bones
+        .iter()
+        .map(|bone| {
+            let source = match counterpart(bone.id) {
+                Some(other) => frame
+                    .bone_by_id(other)
+                    .map(|other| other.trans)
+                    .or_else(|| skeleton.bone_by_id(other).map(|other| other.trans))
+                    .unwrap_or(bone.trans),
+                None => bone.trans,
+            };
+            BoneTrans {
+                id: bone.id,
+                trans: mirror_trans(&source),
+            }
+        })
+        .collect();
+
+    Frame {
+        num: frame.num,
+        time: frame.time,
+        bones,
+    }
+}
+
+fn mirror_trans(trans: &Transform) -> Transform {
+    Transform {
+        rot: Rotation {
+            x: trans.rot.x,
+            y: -trans.rot.y,
+            z: -trans.rot.z,
+            w: trans.rot.w,
+        },
+        pos: Position {
+            x: -trans.pos.x,
+            y: trans.pos.y,
+            z: trans.pos.z,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Bone, ROOT_PARENT};
+    use alloc::vec;
+    use core::f32::consts::FRAC_1_SQRT_2;
+
+    fn trans(x: f32, rot: Rotation) -> Transform {
+        Transform {
+            rot,
+            pos: Position { x, y: 1.0, z: 0.5 },
+        }
+    }
+
+    #[test]
+    fn test_mirror_frame() {
+        let bone = |id, parent, x| Bone {
+            id,
+            parent,
+            trans: trans(x, Rotation::identity()),
+        };
+        // 0がrootで、1が左腕、2が右腕
+        let skeleton = Skeleton {
+            bones: vec![bone(0, ROOT_PARENT, 0.0), bone(1, 0, -0.2), bone(2, 0, 0.2)],
+        };
+        let z90 = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: FRAC_1_SQRT_2,
+            w: FRAC_1_SQRT_2,
+        };
+        let frame = Frame {
+            num: 1,
+            time: 0,
+            bones: vec![
+                BoneTrans {
+                    id: 0,
+                    trans: trans(0.1, Rotation::identity()),
+                },
+                BoneTrans {
+                    id: 1,
+                    trans: trans(-0.2, z90),
+                },
+                BoneTrans {
+                    id: 2,
+                    trans: trans(0.3, Rotation::identity()),
+                },
+            ],
+        };
+        let pairs = [(1, 2)];
+
+        let mirrored = mirror_frame(&frame, &skeleton, &pairs);
+
+        assert_eq!(mirrored.num, 1);
+        assert_eq!(mirrored.bones[0].id, 0);
+        assert_eq!(mirrored.bones[0].trans, trans(-0.1, Rotation::identity()));
+        // 左腕は右腕の鏡像になる
+        assert_eq!(mirrored.bones[1].id, 1);
+        assert_eq!(mirrored.bones[1].trans, trans(-0.3, Rotation::identity()));
+        assert_eq!(mirrored.bones[2].id, 2);
+        assert_eq!(mirrored.bones[2].trans.pos, trans(0.2, z90).pos);
+        assert_eq!(mirrored.bones[2].trans.rot, z90.conjugate());
+
+        // 2回反転すると元に戻る
+        assert_eq!(mirror_frame(&mirrored, &skeleton, &pairs), frame);
+
+        // 右腕がないときはskeletonの右腕を使う
+        let mut partial = frame.clone();
+        partial.bones.pop();
+        let mirrored = mirror_frame(&partial, &skeleton, &pairs);
+        assert_eq!(mirrored.bones.len(), 2);
+        assert_eq!(mirrored.bones[1].trans, trans(-0.2, Rotation::identity()));
+    }
+}
@@ -0,0 +1,79 @@
+//! Conversions to [`nalgebra`] types, enabled by the `nalgebra` feature.
+
+use crate::{Position, Rotation, Transform};
+use ::nalgebra::{Isometry3, Point3, Quaternion, Translation3, UnitQuaternion};
+
+impl From<Rotation> for UnitQuaternion<f32> {
+    /// The quaternion is normalized.
+    fn from(r: Rotation) -> Self {
+        UnitQuaternion::from_quaternion(Quaternion::new(r.w, r.x, r.y, r.z))
+    }
+}
+
+impl From<Position> for Point3<f32> {
+    fn from(p: Position) -> Self {
+        Point3::new(p.x, p.y, p.z)
+    }
+}
+
+impl From<Transform> for Isometry3<f32> {
+    fn from(t: Transform) -> Self {
+        Isometry3::from_parts(Translation3::new(t.pos.x, t.pos.y, t.pos.z), t.rot.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::nalgebra::Vector3;
+
+    #[test]
+    fn test_rotation() {
+        let q: UnitQuaternion<f32> = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: 0.6,
+            w: 0.8,
+        }
+        .into();
+
+        assert_eq!(q.i, 0.0);
+        assert_eq!(q.j, 0.0);
+        assert!((q.k - 0.6).abs() < 1e-6);
+        assert!((q.w - 0.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_position() {
+        let p: Point3<f32> = Position {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+        }
+        .into();
+
+        assert_eq!(p, Point3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn test_transform() {
+        let t = Transform {
+            rot: Rotation {
+                x: 0.0,
+                y: 0.0,
+                z: std::f32::consts::FRAC_1_SQRT_2,
+                w: std::f32::consts::FRAC_1_SQRT_2,
+            },
+            pos: Position {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            },
+        };
+
+        let iso: Isometry3<f32> = t.into();
+        let p = iso.transform_point(&Point3::new(1.0, 0.0, 0.0));
+
+        assert!((p.coords - Vector3::new(1.0, 3.0, 3.0)).norm() < 1e-6);
+    }
+}
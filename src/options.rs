@@ -0,0 +1,92 @@
+use crate::{Endian, DEFAULT_MAX_BONES};
+
+/// The options of [`parse_with_options`](crate::parse_with_options).
+///
+/// The default options parse the packets sent by mocopi strictly, like
+/// [`parse`](crate::parse). Each option is set by the method of the same name.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mocopi_parser::{Endian, ParseOptions};
+///
+/// let opts = ParseOptions::new()
+///     .endian(Endian::Big)
+///     .max_bones(64)
+///     .exact(true);
+///
+/// let buf: Vec<u8> = std::fs::read("packet.bin").unwrap();
+/// let packet = mocopi_parser::parse_with_options(&buf, &opts).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    pub(crate) endian: Endian,
+    pub(crate) max_bones: usize,
+    pub(crate) lenient_format: bool,
+    pub(crate) lenient_names: bool,
+    pub(crate) exact: bool,
+}
+
+impl ParseOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        ParseOptions {
+            endian: Endian::Little,
+            max_bones: DEFAULT_MAX_BONES,
+            lenient_format: false,
+            lenient_names: false,
+            exact: false,
+        }
+    }
+
+    /// The byte order of the numbers, [`Endian::Little`] by default.
+    pub fn endian(mut self, endian: Endian) -> Self {
+        self.endian = endian;
+        self
+    }
+
+    /// The most bones which a skeleton or a frame may have, [`DEFAULT_MAX_BONES`] by default.
+    ///
+    /// More bones fail with [`ParseError::TooManyBones`](crate::ParseError::TooManyBones).
+    pub fn max_bones(mut self, max_bones: usize) -> Self {
+        self.max_bones = max_bones;
+        self
+    }
+
+    /// Skip checking the format and the version in the head, `false` by default.
+    ///
+    /// When set, packets of another format are parsed, and packets of an unknown
+    /// [`Version`](crate::Version) are parsed as [`Version::LATEST`](crate::Version::LATEST),
+    /// like [`parse_lenient`](crate::parse_lenient).
+    pub fn lenient_format(mut self, lenient: bool) -> Self {
+        self.lenient_format = lenient;
+        self
+    }
+
+    /// Accept broken names of values, `false` by default.
+    ///
+    /// When set, a name ends at its first byte which is not printable ASCII, e.g. a NUL or
+    /// a Latin-1 character, and it matches the name which starts with it if it has at least
+    /// 3 bytes. `tra\0` is read as `tran`, while it fails with
+    /// [`ParseError::UnexpectedTag`](crate::ParseError) by default.
+    pub fn lenient_names(mut self, lenient: bool) -> Self {
+        self.lenient_names = lenient;
+        self
+    }
+
+    /// Require the data to be exactly one packet, `false` by default.
+    ///
+    /// When set, bytes left after the packet fail with
+    /// [`ParseError::TrailingBytes`](crate::ParseError::TrailingBytes), like
+    /// [`parse_exact`](crate::parse_exact). Otherwise they are ignored.
+    pub fn exact(mut self, exact: bool) -> Self {
+        self.exact = exact;
+        self
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions::new()
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{BoneId, Position, Rotation, Skeleton, SkeletonError, SkeletonOrFrame};
+use alloc::vec::Vec;
+
+/// The world-space pose of a bone, returned by [`SkeletonOrFrame::to_pose`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JointPose {
+    pub id: BoneId,
+    pub position: Position,
+    pub rotation: Rotation,
+}
+
+impl SkeletonOrFrame {
+    /// Compute the world-space pose of every bone, in the order of the bones of the skeleton.
+    ///
+    /// A skeleton packet uses its own bones. A frame packet applies its transforms in the
+    /// hierarchy of `skeleton`, and bones which are not in the frame keep the transforms of
+    /// the skeleton.
+    pub fn to_pose(&self, skeleton: &Skeleton) -> Result<Vec<JointPose>, SkeletonError> {
+        let world = match self {
+            SkeletonOrFrame::Skeleton(packet) => packet.skeleton.world_transforms()?,
+            SkeletonOrFrame::Frame(packet) => {
+                skeleton.with_frame(&packet.frame).world_transforms()?
+            }
+        };
+
+        Ok(world
+            .into_iter()
+            .map(|(id, trans)| JointPose {
+                id,
+                position: trans.pos,
+                rotation: trans.rot,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{head, info};
+    use crate::{Bone, BoneTrans, Frame, FramePacket, SkeletonPacket, Transform, ROOT_PARENT};
+    use alloc::vec;
+    use core::f32::consts::FRAC_1_SQRT_2;
+
+    fn chain() -> Skeleton {
+        let offset = Transform {
+            rot: Rotation::identity(),
+            pos: Position {
+                x: 1.0,
+                y: 0.0,
+                z: 0.0,
+            },
+        };
+        Skeleton {
+            bones: vec![
+                Bone {
+                    id: 0,
+                    parent: ROOT_PARENT,
+                    trans: offset,
+                },
+                Bone {
+                    id: 1,
+                    parent: 0,
+                    trans: offset,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_to_pose() {
+        let skeleton = chain();
+
+        let packet = SkeletonOrFrame::Skeleton(SkeletonPacket {
+            head: head(),
+            info: info(),
+            skeleton: skeleton.clone(),
+            extra: Vec::new(),
+        });
+        let pose = packet.to_pose(&Skeleton { bones: Vec::new() }).unwrap();
+        assert_eq!(pose.len(), 2);
+        assert_eq!(pose[1].id, 1);
+        assert_eq!(pose[1].position.x, 2.0);
+        assert_eq!(pose[1].rotation, Rotation::identity());
+
+        // rootをZ軸まわりに90°回転させると、子は+Y方向に移る
+        let z90 = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: FRAC_1_SQRT_2,
+            w: FRAC_1_SQRT_2,
+        };
+        let packet = SkeletonOrFrame::Frame(FramePacket {
+            head: head(),
+            info: info(),
+            frame: Frame {
+                num: 1,
+                time: 0,
+                bones: vec![BoneTrans {
+                    id: 0,
+                    trans: Transform {
+                        rot: z90,
+                        pos: Position::zero(),
+                    },
+                }],
+            },
+            extra: Vec::new(),
+        });
+        let pose = packet.to_pose(&skeleton).unwrap();
+
+        assert_eq!(pose[0].id, 0);
+        assert_eq!(pose[0].position, Position::zero());
+        assert_eq!(pose[0].rotation, z90);
+        assert_eq!(pose[1].id, 1);
+        assert!(pose[1].position.x.abs() < 1e-6);
+        assert!((pose[1].position.y - 1.0).abs() < 1e-6);
+        assert!(pose[1].rotation.approx_eq(&z90, 1e-6));
+    }
+
+    #[test]
+    fn test_to_pose_cycle() {
+        let mut skeleton = chain();
+        skeleton.bones[0].parent = 1;
+        let packet = SkeletonOrFrame::Skeleton(SkeletonPacket {
+            head: head(),
+            info: info(),
+            skeleton,
+            extra: Vec::new(),
+        });
+
+        assert!(matches!(
+            packet.to_pose(&chain()),
+            Err(SkeletonError::Cycle(_))
+        ));
+    }
+}
@@ -0,0 +1,93 @@
+use crate::Frame;
+
+/// How much each new measurement moves the estimate of a [`FrameRateEstimator`].
+const SMOOTHING: f32 = 0.1;
+
+/// Estimate the frame rate from the numbers and the times of frames.
+///
+/// The rate between each frame and the newest one before it is smoothed with an exponential
+/// moving average. Frame numbers and times may wrap around, and a frame which is older than the
+/// newest one, e.g. a reordered datagram, is ignored.
+#[derive(Debug, Clone, Default)]
+pub struct FrameRateEstimator {
+    last: Option<(u32, u32)>,
+    fps: Option<f32>,
+}
+
+impl FrameRateEstimator {
+    /// Create an estimator which has not observed any frames.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update the estimate with the frame.
+    pub fn observe(&mut self, frame: &Frame) {
+        let Some((num, time)) = self.last else {
+            self.last = Some((frame.num, frame.time));
+            return;
+        };
+
+        // 差が半分を超えるときは古いframeとみなす
+        let frames = frame.num.wrapping_sub(num);
+        if frames == 0 || frames > u32::MAX / 2 {
+            return;
+        }
+        self.last = Some((frame.num, frame.time));
+
+        let millis = frame.time.wrapping_sub(time);
+        if millis == 0 || millis > u32::MAX / 2 {
+            return;
+        }
+        let fps = frames as f32 * 1000.0 / millis as f32;
+        self.fps = Some(match self.fps {
+            Some(prev) => prev + (fps - prev) * SMOOTHING,
+            None => fps,
+        });
+    }
+
+    /// The estimated frames per second, or `None` until two frames have been observed.
+    pub fn fps(&self) -> Option<f32> {
+        self.fps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn frame(num: u32, time: u32) -> Frame {
+        Frame {
+            num,
+            time,
+            bones: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_frame_rate() {
+        let mut estimator = FrameRateEstimator::new();
+        assert_eq!(estimator.fps(), None);
+
+        // 50fpsで届く
+        for i in 0..10 {
+            estimator.observe(&frame(100 + i, 1000 + i * 20));
+        }
+        assert!((estimator.fps().unwrap() - 50.0).abs() < 1e-3);
+
+        // 遅れて届いたframeは無視する
+        estimator.observe(&frame(105, 1100));
+        assert!((estimator.fps().unwrap() - 50.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_frame_rate_wraparound() {
+        let mut estimator = FrameRateEstimator::new();
+        estimator.observe(&frame(u32::MAX - 1, u32::MAX - 20));
+        estimator.observe(&frame(u32::MAX, u32::MAX));
+        estimator.observe(&frame(0, 19));
+        estimator.observe(&frame(2, 59));
+
+        assert!((estimator.fps().unwrap() - 50.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,235 @@
+use crate::iter::{packet_extent, PacketExtent};
+use crate::{parse, ParseError, SkeletonOrFrame};
+use alloc::vec::Vec;
+use std::io::{ErrorKind, Read};
+
+/// The number of bytes a [`PacketReader`] asks the stream for at once.
+const CHUNK_LEN: usize = 4096;
+
+/// Read from the stream into `buf` until it starts with a whole packet, and return the length
+/// of the packet.
+///
+/// When `exact` is set, no byte after the packet is read, so the packet ends after `skdf` or
+/// `fram`. Otherwise the stream is read in chunks, and `buf` may get the start of the next
+/// packet, which the caller keeps for it.
+fn read_packet<R: Read>(r: &mut R, buf: &mut Vec<u8>, exact: bool) -> Result<usize, ParseError> {
+    let mut eof = false;
+    loop {
+        let needed = match packet_extent(buf, eof)? {
+            PacketExtent::Complete(len) => return Ok(len),
+            PacketExtent::Partial(_) if eof && buf.is_empty() => {
+                return Err(ParseError::EndOfStream)
+            }
+            PacketExtent::Partial(_) if eof => return Err(ParseError::StreamTruncated),
+            PacketExtent::Partial(len) => len,
+        };
+
+        if exact {
+            // 宣言された長さの分を先に確保しないよう、読めた分だけ増やす
+            let want = (needed - buf.len()) as u64;
+            let read = r
+                .take(want)
+                .read_to_end(buf)
+                .map_err(|e| ParseError::Io(e.kind()))?;
+            eof = (read as u64) < want;
+        } else {
+            // 届いている分だけを読み、次のpacketを待たない
+            let start = buf.len();
+            buf.resize(start + CHUNK_LEN, 0);
+            let read = loop {
+                match r.read(&mut buf[start..]) {
+                    Ok(n) => break n,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        buf.truncate(start);
+                        return Err(ParseError::Io(e.kind()));
+                    }
+                }
+            };
+            buf.truncate(start + read);
+            eof = read == 0;
+        }
+    }
+}
+
+/// Read one packet from the stream and parse it.
+///
+/// Returns [`ParseError::EndOfStream`] if the stream ends before the packet starts, and
+/// [`ParseError::StreamTruncated`] if it ends in the middle of the packet.
+///
+/// No byte after the packet is read, so the sections after `skdf` or `fram` are not read
+/// either. Use [`PacketReader`] for a stream whose packets have them.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mocopi_parser::ParseError;
+/// use std::fs::File;
+///
+/// let mut file = File::open("capture.bin").unwrap();
+///
+/// loop {
+///     match mocopi_parser::parse_reader(&mut file) {
+///         Ok(packet) => { /* ... */ }
+///         Err(ParseError::EndOfStream) => break,
+///         Err(e) => panic!("{}", e),
+///     }
+/// }
+/// ```
+pub fn parse_reader<R: Read>(r: &mut R) -> Result<SkeletonOrFrame, ParseError> {
+    let mut buf = Vec::new();
+    let len = read_packet(r, &mut buf, true)?;
+    parse(&buf[..len])
+}
+
+/// Read packets one by one from a stream, including the sections after `skdf` or `fram`.
+///
+/// The stream is read in chunks, and the bytes read past a packet are kept for the next one.
+/// A packet which ends right at the end of what has been read is parsed without waiting for
+/// the next packet, so its extra sections must arrive together with it.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mocopi_parser::{PacketReader, ParseError};
+/// use std::net::TcpStream;
+///
+/// let stream = TcpStream::connect("192.168.10.2:12351").unwrap();
+/// let mut reader = PacketReader::new(stream);
+///
+/// loop {
+///     match reader.read_packet() {
+///         Ok(packet) => { /* ... */ }
+///         Err(ParseError::EndOfStream) => break,
+///         Err(e) => panic!("{}", e),
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PacketReader<R: Read> {
+    reader: R,
+    /// The bytes read after the last packet.
+    buf: Vec<u8>,
+}
+
+impl<R: Read> PacketReader<R> {
+    /// Create a packet reader which reads from `reader`.
+    pub fn new(reader: R) -> Self {
+        PacketReader {
+            reader,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Read the next packet and parse it.
+    ///
+    /// Returns the same errors as [`parse_reader`]. The packet is consumed even if it cannot
+    /// be parsed.
+    pub fn read_packet(&mut self) -> Result<SkeletonOrFrame, ParseError> {
+        let len = read_packet(&mut self.reader, &mut self.buf, false)?;
+        let result = parse(&self.buf[..len]);
+        self.buf.drain(..len);
+        result
+    }
+
+    /// Take the reader back. The bytes read after the last packet are lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::frame_packet;
+    use crate::{encode_frame, FramePacket};
+
+    /// A stream which returns at most `len` bytes per read, like a socket.
+    struct Chunks<'a> {
+        data: &'a [u8],
+        len: usize,
+    }
+
+    impl Read for Chunks<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.len.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_parse_reader() {
+        let raw = [
+            encode_frame(&frame_packet(1)).unwrap(),
+            encode_frame(&frame_packet(2)).unwrap(),
+        ]
+        .concat();
+        let mut reader = &raw[..];
+
+        for num in 1..=2 {
+            match parse_reader(&mut reader).unwrap() {
+                SkeletonOrFrame::Frame(packet) => assert_eq!(packet.frame.num, num),
+                SkeletonOrFrame::Skeleton(_) => panic!("expected a frame packet"),
+            }
+        }
+
+        assert_eq!(
+            parse_reader(&mut reader).err(),
+            Some(ParseError::EndOfStream)
+        );
+    }
+
+    #[test]
+    fn test_parse_reader_truncated() {
+        let raw = encode_frame(&frame_packet(1)).unwrap();
+        let mut reader = &raw[..raw.len() - 3];
+
+        assert_eq!(
+            parse_reader(&mut reader).err(),
+            Some(ParseError::StreamTruncated)
+        );
+    }
+
+    #[test]
+    fn test_packet_reader() {
+        let with_extra = |num| FramePacket {
+            extra: vec![("xtra".to_string(), vec![0x01, 0x02, 0x03])],
+            ..frame_packet(num)
+        };
+        let raw = [
+            encode_frame(&with_extra(1)).unwrap(),
+            encode_frame(&with_extra(2)).unwrap(),
+            encode_frame(&frame_packet(3)).unwrap(),
+        ]
+        .concat();
+
+        // 一度に全部読めても、少しずつ届いても同じ
+        for len in [raw.len(), 7] {
+            let mut reader = PacketReader::new(Chunks { data: &raw, len });
+
+            assert_eq!(
+                reader.read_packet().unwrap().as_frame(),
+                Some(&with_extra(1))
+            );
+            assert_eq!(
+                reader.read_packet().unwrap().as_frame(),
+                Some(&with_extra(2))
+            );
+            assert_eq!(
+                reader.read_packet().unwrap().as_frame(),
+                Some(&frame_packet(3))
+            );
+            assert_eq!(reader.read_packet().err(), Some(ParseError::EndOfStream));
+        }
+
+        let mut reader = PacketReader::new(&raw[..raw.len() - 3]);
+        reader.read_packet().unwrap();
+        reader.read_packet().unwrap();
+        assert_eq!(
+            reader.read_packet().err(),
+            Some(ParseError::StreamTruncated)
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use crate::iter::{packet_extent, PacketExtent};
+use crate::{parse_with_len, ParseError, SkeletonOrFrame};
+use alloc::vec::Vec;
+
+/// Buffer chunks of a stream until whole packets are available and parse them.
+///
+/// # Examples
+///
+/// ```
+/// let mut reassembler = mocopi_parser::Reassembler::new();
+///
+/// # let chunks: Vec<Vec<u8>> = Vec::new();
+/// for chunk in chunks {
+///     reassembler.push(&chunk);
+///     while let Some(packet) = reassembler.try_parse() {
+///         // ...
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Reassembler {
+    buf: Vec<u8>,
+    capacity: usize,
+    overflowed: bool,
+}
+
+impl Reassembler {
+    /// The maximum number of bytes which [`Reassembler::new`] buffers, 64 KiB.
+    pub const DEFAULT_CAPACITY: usize = 64 * 1024;
+
+    /// Create a reassembler which buffers up to [`Reassembler::DEFAULT_CAPACITY`] bytes.
+    pub fn new() -> Self {
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
+    }
+
+    /// Create a reassembler which buffers up to `capacity` bytes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Reassembler {
+            buf: Vec::new(),
+            capacity,
+            overflowed: false,
+        }
+    }
+
+    /// The number of buffered bytes.
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Whether no bytes are buffered.
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Append a chunk of the stream.
+    ///
+    /// If the buffer would exceed the capacity, the buffered bytes are discarded and the next
+    /// [`Reassembler::try_parse`] returns [`ParseError::BufferOverflow`].
+    pub fn push(&mut self, chunk: &[u8]) {
+        if self.buf.len() + chunk.len() > self.capacity {
+            self.buf.clear();
+            self.overflowed = true;
+            if chunk.len() > self.capacity {
+                return;
+            }
+        }
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// Parse the next packet if it has been fully buffered.
+    ///
+    /// Returns `None` while more bytes are needed. When the buffered bytes cannot be a packet,
+    /// they are discarded and the error is returned.
+    ///
+    /// The packet also waits for the sections after `skdf` or `fram`. A packet which ends
+    /// right at the end of the buffered bytes is parsed at once, so its extra sections must be
+    /// pushed in the same chunk as the end of the packet.
+    pub fn try_parse(&mut self) -> Option<Result<SkeletonOrFrame, ParseError>> {
+        if self.overflowed {
+            self.overflowed = false;
+            return Some(Err(self.overflow_error()));
+        }
+        if self.buf.is_empty() {
+            return None;
+        }
+
+        let len = match packet_extent(&self.buf, false) {
+            Ok(PacketExtent::Complete(len)) => len,
+            Ok(PacketExtent::Partial(len)) => {
+                if len > self.capacity {
+                    self.buf.clear();
+                    return Some(Err(self.overflow_error()));
+                }
+                return None;
+            }
+            Err(e) => {
+                self.buf.clear();
+                return Some(Err(e));
+            }
+        };
+
+        let result = parse_with_len(&self.buf[..len]).map(|(_, packet)| packet);
+        self.buf.drain(..len);
+        Some(result)
+    }
+
+    fn overflow_error(&self) -> ParseError {
+        ParseError::BufferOverflow {
+            capacity: self.capacity,
+        }
+    }
+}
+
+impl Default for Reassembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode_frame, test_util};
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn frame_packet(num: u32) -> Vec<u8> {
+        encode_frame(&test_util::frame_packet(num)).unwrap()
+    }
+
+    #[test]
+    fn test_reassemble() {
+        let raw = [frame_packet(1), frame_packet(2)].concat();
+        let mut reassembler = Reassembler::new();
+        let mut nums = Vec::new();
+
+        // 7bytesずつ届く
+        for chunk in raw.chunks(7) {
+            reassembler.push(chunk);
+            while let Some(packet) = reassembler.try_parse() {
+                nums.push(packet.unwrap().as_frame().unwrap().frame.num);
+            }
+        }
+
+        assert_eq!(nums, [1, 2]);
+        assert!(reassembler.is_empty());
+    }
+
+    #[test]
+    fn test_reassemble_extra() {
+        let extra = [0x03, 0x00, 0x00, 0x00, b'x', b't', b'r', b'a', 0x01, 0x02, 0x03];
+        let first = [frame_packet(1), extra.to_vec()].concat();
+        let raw = [first.clone(), frame_packet(2)].concat();
+        let mut reassembler = Reassembler::new();
+
+        // 後に続くsectionの途中まで届いている
+        reassembler.push(&first[..first.len() - 2]);
+        assert_eq!(reassembler.try_parse(), None);
+
+        reassembler.push(&raw[first.len() - 2..]);
+        let packet = reassembler.try_parse().unwrap().unwrap();
+        assert_eq!(
+            packet.as_frame().unwrap().extra,
+            [("xtra".to_string(), vec![0x01, 0x02, 0x03])]
+        );
+        let packet = reassembler.try_parse().unwrap().unwrap();
+        assert_eq!(packet.as_frame().unwrap().frame.num, 2);
+        assert!(reassembler.is_empty());
+    }
+
+    #[test]
+    fn test_reassemble_overflow() {
+        let mut reassembler = Reassembler::with_capacity(16);
+
+        reassembler.push(&[0x00; 10]);
+        reassembler.push(&[0x00; 10]);
+
+        assert_eq!(
+            reassembler.try_parse(),
+            Some(Err(ParseError::BufferOverflow { capacity: 16 }))
+        );
+        assert_eq!(reassembler.len(), 10);
+
+        // 宣言された長さがcapacityを超える
+        let mut reassembler = Reassembler::with_capacity(32);
+        reassembler.push(&frame_packet(1)[..12]);
+
+        assert_eq!(
+            reassembler.try_parse(),
+            Some(Err(ParseError::BufferOverflow { capacity: 32 }))
+        );
+        assert!(reassembler.is_empty());
+    }
+}
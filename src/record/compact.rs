@@ -0,0 +1,326 @@
+//! A compact binary layout for recordings, much smaller than the packets from mocopi.
+//!
+//! A compact recording is one skeleton followed by frames, without the names of the sections,
+//! the head and the info. All the values are little-endian:
+//!
+//! | Bytes | Value |
+//! |---|---|
+//! | 4 | [`MAGIC`] |
+//! | 1 | [`VERSION`] |
+//! | 2 | The number of bones in the skeleton, `n` |
+//! | 32 × `n` | The bones: `bnid` and `pbid` as `u16`, and `tran` as 7 `f32` |
+//! | | The frames, until the end |
+//!
+//! Each frame is:
+//!
+//! | Bytes | Value |
+//! |---|---|
+//! | 4 | `fnum` |
+//! | 4 | `time` |
+//! | 2 | The number of bones in the frame, `m` |
+//! | 2 × `m` | The `bnid` of each bone |
+//! | 28 × `m` | The `tran` of each bone, as 7 `f32` each |
+//!
+//! A `tran` is the rotation `x`, `y`, `z`, `w` followed by the position `x`, `y`, `z`.
+
+use crate::{Bone, BoneTrans, Frame, ParseError, Position, Rotation, Skeleton, Transform};
+use alloc::string::String;
+use alloc::vec::Vec;
+use std::io::{self, ErrorKind};
+
+/// The bytes at the start of a compact recording.
+pub const MAGIC: [u8; 4] = *b"mcpc";
+
+/// The version of the layout, written after [`MAGIC`].
+pub const VERSION: u8 = 1;
+
+/// Encode a skeleton and its frames into a compact recording.
+///
+/// Returns an error of [`ErrorKind::InvalidInput`] if the skeleton or a frame has more than
+/// `u16::MAX` bones.
+pub fn encode(skeleton: &Skeleton, frames: &[Frame]) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&MAGIC);
+    buf.push(VERSION);
+    buf.extend_from_slice(&bone_count(skeleton.bones.len())?.to_le_bytes());
+    for bone in &skeleton.bones {
+        buf.extend_from_slice(&bone.id.to_le_bytes());
+        buf.extend_from_slice(&bone.parent.to_le_bytes());
+        write_trans(&mut buf, &bone.trans);
+    }
+    for frame in frames {
+        encode_frame(&mut buf, frame)?;
+    }
+    Ok(buf)
+}
+
+/// Append a frame to a compact recording, e.g. while recording a stream.
+///
+/// Returns an error of [`ErrorKind::InvalidInput`] if the frame has more than `u16::MAX`
+/// bones, and then `buf` is left as it was.
+pub fn encode_frame(buf: &mut Vec<u8>, frame: &Frame) -> io::Result<()> {
+    // 書き始める前に確かめる
+    let count = bone_count(frame.bones.len())?;
+    buf.extend_from_slice(&frame.num.to_le_bytes());
+    buf.extend_from_slice(&frame.time.to_le_bytes());
+    buf.extend_from_slice(&count.to_le_bytes());
+    for bone in &frame.bones {
+        buf.extend_from_slice(&bone.id.to_le_bytes());
+    }
+    // floatは全部のIDの後ろにまとめる
+    for bone in &frame.bones {
+        write_trans(buf, &bone.trans);
+    }
+    Ok(())
+}
+
+/// Decode a compact recording into the skeleton and its frames.
+pub fn decode(data: &[u8]) -> Result<(Skeleton, Vec<Frame>), ParseError> {
+    let mut reader = Reader { data, offset: 0 };
+
+    let magic = reader.take(MAGIC.len())?;
+    if magic != MAGIC {
+        return Err(ParseError::UnknownFormat(
+            String::from_utf8_lossy(magic).into_owned(),
+        ));
+    }
+    let ver = reader.take(1)?[0];
+    if ver != VERSION {
+        return Err(ParseError::UnsupportedVersion(ver));
+    }
+
+    let count = reader.u16()?;
+    let mut bones = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        bones.push(Bone {
+            id: reader.u16()?,
+            parent: reader.u16()?,
+            trans: reader.trans()?,
+        });
+    }
+
+    let mut frames = Vec::new();
+    while reader.offset < data.len() {
+        let num = reader.u32()?;
+        let time = reader.u32()?;
+        let count = reader.u16()?;
+        let ids = (0..count)
+            .map(|_| reader.u16())
+            .collect::<Result<Vec<_>, _>>()?;
+        let bones = ids
+            .into_iter()
+            .map(|id| {
+                Ok(BoneTrans {
+                    id,
+                    trans: reader.trans()?,
+                })
+            })
+            .collect::<Result<_, ParseError>>()?;
+        frames.push(Frame { num, time, bones });
+    }
+
+    Ok((Skeleton { bones }, frames))
+}
+
+/// The number of bones as written before them.
+fn bone_count(len: usize) -> io::Result<u16> {
+    u16::try_from(len)
+        .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "more than u16::MAX bones"))
+}
+
+fn write_trans(buf: &mut Vec<u8>, trans: &Transform) {
+    let values = [
+        trans.rot.x,
+        trans.rot.y,
+        trans.rot.z,
+        trans.rot.w,
+        trans.pos.x,
+        trans.pos.y,
+        trans.pos.z,
+    ];
+    for v in values {
+        buf.extend_from_slice(&v.to_le_bytes());
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ParseError> {
+        let rem = &self.data[self.offset..];
+        if rem.len() < len {
+            return Err(ParseError::UnexpectedEof {
+                offset: self.offset,
+                expected: len,
+                found: rem.len(),
+            });
+        }
+        self.offset += len;
+        Ok(&rem[..len])
+    }
+
+    fn u16(&mut self) -> Result<u16, ParseError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, ParseError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn f32(&mut self) -> Result<f32, ParseError> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn trans(&mut self) -> Result<Transform, ParseError> {
+        Ok(Transform {
+            rot: Rotation {
+                x: self.f32()?,
+                y: self.f32()?,
+                z: self.f32()?,
+                w: self.f32()?,
+            },
+            pos: Position {
+                x: self.f32()?,
+                y: self.f32()?,
+                z: self.f32()?,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{head, info};
+    use crate::{encode_frame as encode_packet, FramePacket, ROOT_PARENT};
+
+    fn trans(i: u16) -> Transform {
+        Transform {
+            rot: Rotation {
+                x: 0.1 * i as f32,
+                y: 0.2,
+                z: -0.3,
+                w: 0.9,
+            },
+            pos: Position {
+                x: i as f32,
+                y: -1.5,
+                z: 0.25,
+            },
+        }
+    }
+
+    fn skeleton() -> Skeleton {
+        Skeleton {
+            bones: (0..27)
+                .map(|id| Bone {
+                    id,
+                    parent: if id == 0 { ROOT_PARENT } else { id - 1 },
+                    trans: trans(id),
+                })
+                .collect(),
+        }
+    }
+
+    fn frame(num: u32) -> Frame {
+        Frame {
+            num,
+            time: num * 20,
+            bones: (0..27)
+                .map(|id| BoneTrans {
+                    id,
+                    trans: trans(id + num as u16),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let skeleton = skeleton();
+        let mut frames: Vec<Frame> = (0..10).map(frame).collect();
+        // boneの数が違うframeも戻せる
+        frames[3].bones.truncate(5);
+        frames[4].bones.clear();
+
+        let data = encode(&skeleton, &frames).unwrap();
+        assert_eq!(decode(&data).unwrap(), (skeleton.clone(), frames.clone()));
+
+        // 後からframeを足せる
+        let mut data = encode(&skeleton, &[]).unwrap();
+        for frame in &frames {
+            encode_frame(&mut data, frame).unwrap();
+        }
+        assert_eq!(decode(&data).unwrap(), (skeleton, frames));
+    }
+
+    #[test]
+    fn test_smaller_than_packets() {
+        let frames: Vec<Frame> = (0..10).map(frame).collect();
+        let raw: usize = frames
+            .iter()
+            .map(|frame| {
+                encode_packet(&FramePacket {
+                    head: head(),
+                    info: info(),
+                    frame: frame.clone(),
+                    extra: Vec::new(),
+                })
+                .unwrap()
+                .len()
+            })
+            .sum();
+
+        let compact = encode(&Skeleton { bones: Vec::new() }, &frames)
+            .unwrap()
+            .len();
+        // 27 bonesでおよそ半分になる
+        assert!(
+            compact * 5 < raw * 3,
+            "{} bytes, {} as packets",
+            compact,
+            raw
+        );
+    }
+
+    #[test]
+    fn test_too_many_bones() {
+        let mut data = encode(&skeleton(), &[frame(1)]).unwrap();
+        let len = data.len();
+        let mut frame = frame(2);
+        frame.bones = vec![frame.bones[0].clone(); u16::MAX as usize + 1];
+
+        let err = encode_frame(&mut data, &frame).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert_eq!(data.len(), len);
+
+        let err = encode(&skeleton(), &[frame]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_decode_error() {
+        let data = encode(&skeleton(), &[frame(1)]).unwrap();
+
+        assert!(matches!(
+            decode(b"abcd\x01\x00\x00"),
+            Err(ParseError::UnknownFormat(_))
+        ));
+        let mut bad = data.clone();
+        bad[4] = 2;
+        assert_eq!(decode(&bad), Err(ParseError::UnsupportedVersion(2)));
+
+        // 途中で切れたframe
+        assert_eq!(
+            decode(&data[..data.len() - 1]),
+            Err(ParseError::UnexpectedEof {
+                offset: data.len() - 4,
+                expected: 4,
+                found: 3,
+            })
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Record packets into a file and play them back.
+//!
+//! A recording is the packets written back to back as mocopi sends them. Each top-level
+//! section starts with its length, so the packets can be read again with
+//! [`PacketReader`] or [`Player`]. [`compact`] stores them in much less space.
+
+pub mod compact;
+
+use crate::iter::packet_len;
+use crate::{encode_frame, encode_skeleton, PacketReader, ParseError, SkeletonOrFrame};
+use std::io::{self, ErrorKind, Read, Write};
+
+/// Write packets to a recording.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mocopi_parser::record::Recorder;
+/// use std::fs::File;
+/// use std::net::UdpSocket;
+///
+/// let socket = UdpSocket::bind("192.168.10.1:12351").unwrap();
+/// let mut recorder = Recorder::new(File::create("capture.bin").unwrap());
+/// let mut buf = [0; 2048];
+///
+/// loop {
+///     let (len, _) = socket.recv_from(&mut buf).unwrap();
+///     recorder.record_raw(&buf[..len]).unwrap();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Recorder<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Create a recorder which writes to `writer`.
+    pub fn new(writer: W) -> Self {
+        Recorder { writer }
+    }
+
+    /// Encode the packet and write it.
+    ///
+    /// Returns an error of [`ErrorKind::InvalidInput`] if the packet cannot be encoded.
+    pub fn record(&mut self, packet: &SkeletonOrFrame) -> io::Result<()> {
+        let raw = match packet {
+            SkeletonOrFrame::Skeleton(packet) => encode_skeleton(packet),
+            SkeletonOrFrame::Frame(packet) => encode_frame(packet),
+        }
+        .map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))?;
+        self.writer.write_all(&raw)
+    }
+
+    /// Write a packet as received from mocopi.
+    ///
+    /// Returns an error of [`ErrorKind::InvalidData`] unless `data` is exactly one packet, so
+    /// that the recording can be read back.
+    pub fn record_raw(&mut self, data: &[u8]) -> io::Result<()> {
+        let len = packet_len(data).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+        if len != data.len() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                ParseError::BadLength,
+            ));
+        }
+        self.writer.write_all(data)
+    }
+
+    /// Flush the writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Take the writer back.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// An iterator over the packets in a recording.
+///
+/// The iteration ends at the end of the stream. Unlike [`iter_packets`](crate::iter_packets),
+/// it cannot skip a broken packet, so it also ends after the first error.
+#[derive(Debug)]
+pub struct Player<R: Read> {
+    reader: PacketReader<R>,
+    done: bool,
+}
+
+impl<R: Read> Player<R> {
+    /// Create a player which reads from `reader`.
+    pub fn new(reader: R) -> Self {
+        Player {
+            reader: PacketReader::new(reader),
+            done: false,
+        }
+    }
+}
+
+impl<R: Read> Iterator for Player<R> {
+    type Item = Result<SkeletonOrFrame, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.reader.read_packet() {
+            Ok(packet) => Some(Ok(packet)),
+            Err(ParseError::EndOfStream) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::frame_packet;
+    use crate::FramePacket;
+
+    #[test]
+    fn test_record() {
+        let mut recorder = Recorder::new(Vec::new());
+        recorder
+            .record(&SkeletonOrFrame::Frame(frame_packet(1)))
+            .unwrap();
+        recorder
+            .record_raw(&encode_frame(&frame_packet(2)).unwrap())
+            .unwrap();
+        recorder
+            .record(&SkeletonOrFrame::Frame(frame_packet(3)))
+            .unwrap();
+        let raw = recorder.into_inner();
+
+        let packets: Vec<SkeletonOrFrame> = Player::new(&raw[..]).map(|p| p.unwrap()).collect();
+
+        assert_eq!(packets.len(), 3);
+        for (packet, num) in packets.iter().zip(1..) {
+            assert_eq!(packet.as_frame(), Some(&frame_packet(num)));
+        }
+    }
+
+    #[test]
+    fn test_record_extra() {
+        let with_extra = |num| FramePacket {
+            extra: vec![("xtra".to_string(), vec![0x01, 0x02, 0x03])],
+            ..frame_packet(num)
+        };
+        let mut recorder = Recorder::new(Vec::new());
+        recorder
+            .record(&SkeletonOrFrame::Frame(with_extra(1)))
+            .unwrap();
+        recorder
+            .record_raw(&encode_frame(&with_extra(2)).unwrap())
+            .unwrap();
+        recorder
+            .record(&SkeletonOrFrame::Frame(frame_packet(3)))
+            .unwrap();
+        let raw = recorder.into_inner();
+
+        let packets: Vec<SkeletonOrFrame> = Player::new(&raw[..]).map(|p| p.unwrap()).collect();
+
+        assert_eq!(packets.len(), 3);
+        assert_eq!(packets[0].as_frame(), Some(&with_extra(1)));
+        assert_eq!(packets[1].as_frame(), Some(&with_extra(2)));
+        assert_eq!(packets[2].as_frame(), Some(&frame_packet(3)));
+    }
+
+    #[test]
+    fn test_record_raw_partial() {
+        let raw = encode_frame(&frame_packet(1)).unwrap();
+        let mut recorder = Recorder::new(Vec::new());
+
+        let err = recorder.record_raw(&raw[..raw.len() - 4]).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(recorder.into_inner().is_empty());
+    }
+}
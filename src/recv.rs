@@ -0,0 +1,56 @@
+use crate::{parse, ParseError, SkeletonOrFrame};
+use tokio::net::UdpSocket;
+
+/// Wait for a datagram on the socket and parse it.
+///
+/// `buf` must be large enough to hold a whole packet.
+///
+/// # Examples
+///
+/// ```no_run
+/// use tokio::net::UdpSocket;
+///
+/// # async fn run() {
+/// let socket = UdpSocket::bind("0.0.0.0:12351").await.unwrap();
+/// let mut buf = [0u8; 2048];
+///
+/// loop {
+///     let packet = mocopi_parser::recv_parse(&socket, &mut buf).await.unwrap();
+///     println!("{:?}", packet);
+/// }
+/// # }
+/// ```
+pub async fn recv_parse(socket: &UdpSocket, buf: &mut [u8]) -> Result<SkeletonOrFrame, ParseError> {
+    let (len, _) = socket
+        .recv_from(buf)
+        .await
+        .map_err(|e| ParseError::Io(e.kind()))?;
+
+    parse(&buf[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode_frame;
+    use crate::test_util::frame_packet;
+
+    #[tokio::test]
+    async fn test_recv_parse() {
+        let packet = frame_packet(1);
+        let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        sender
+            .send_to(
+                &encode_frame(&packet).unwrap(),
+                receiver.local_addr().unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let mut buf = [0u8; 2048];
+        let parsed = recv_parse(&receiver, &mut buf).await.unwrap();
+
+        assert_eq!(parsed.as_frame(), Some(&packet));
+    }
+}
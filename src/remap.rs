@@ -0,0 +1,133 @@
+//! Translate bone IDs between skeletons with different numberings.
+
+use crate::{Bone, BoneId, BoneTrans, Frame, Skeleton, ROOT_PARENT};
+use std::collections::HashMap;
+
+/// Replace the bone IDs of the frame through `map`.
+///
+/// Bones which have no entry in `map` keep their ID if `keep_unmapped` is `true`, and are
+/// dropped otherwise.
+pub fn remap_frame(frame: &Frame, map: &HashMap<BoneId, BoneId>, keep_unmapped: bool) -> Frame {
+    Frame {
+        num: frame.num,
+        time: frame.time,
+        bones: frame
+            .bones
+            .iter()
+            .filter_map(|bone| {
+                remap_id(bone.id, map, keep_unmapped).map(|id| BoneTrans {
+                    id,
+                    trans: bone.trans,
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Replace the bone IDs and the parents of the skeleton through `map`.
+///
+/// Bones are kept or dropped like in [`remap_frame`]. A parent without an entry keeps its ID
+/// either way, so a bone whose parent was dropped refers to a missing parent, which
+/// [`Skeleton::validate`] reports.
+pub fn remap_skeleton(
+    skeleton: &Skeleton,
+    map: &HashMap<BoneId, BoneId>,
+    keep_unmapped: bool,
+) -> Skeleton {
+    Skeleton {
+        bones: skeleton
+            .bones
+            .iter()
+            .filter_map(|bone| {
+                remap_id(bone.id, map, keep_unmapped).map(|id| Bone {
+                    id,
+                    parent: match bone.parent {
+                        ROOT_PARENT => ROOT_PARENT,
+                        parent => map.get(&parent).copied().unwrap_or(parent),
+                    },
+                    trans: bone.trans,
+                })
+            })
+            .collect(),
+    }
+}
+
+fn remap_id(id: BoneId, map: &HashMap<BoneId, BoneId>, keep_unmapped: bool) -> Option<BoneId> {
+    match map.get(&id) {
+        Some(&mapped) => Some(mapped),
+        None if keep_unmapped => Some(id),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Position, Rotation, Transform};
+
+    fn trans(x: f32) -> Transform {
+        Transform {
+            rot: Rotation::identity(),
+            pos: Position { x, y: 0.0, z: 0.0 },
+        }
+    }
+
+    fn map() -> HashMap<BoneId, BoneId> {
+        HashMap::from([(0, 10), (1, 11)])
+    }
+
+    #[test]
+    fn test_remap_frame() {
+        let frame = Frame {
+            num: 1,
+            time: 1000,
+            bones: (0..3)
+                .map(|id| BoneTrans {
+                    id,
+                    trans: trans(id as f32),
+                })
+                .collect(),
+        };
+
+        let kept = remap_frame(&frame, &map(), true);
+        let ids: Vec<BoneId> = kept.bones.iter().map(|b| b.id).collect();
+        assert_eq!(ids, [10, 11, 2]);
+        assert_eq!(kept.bones[1].trans, trans(1.0));
+        assert_eq!((kept.num, kept.time), (1, 1000));
+
+        let dropped = remap_frame(&frame, &map(), false);
+        let ids: Vec<BoneId> = dropped.bones.iter().map(|b| b.id).collect();
+        assert_eq!(ids, [10, 11]);
+    }
+
+    #[test]
+    fn test_remap_skeleton() {
+        let skeleton = Skeleton {
+            bones: vec![
+                Bone {
+                    id: 0,
+                    parent: ROOT_PARENT,
+                    trans: trans(0.0),
+                },
+                Bone {
+                    id: 1,
+                    parent: 0,
+                    trans: trans(1.0),
+                },
+                Bone {
+                    id: 2,
+                    parent: 1,
+                    trans: trans(2.0),
+                },
+            ],
+        };
+
+        let remapped = remap_skeleton(&skeleton, &map(), true);
+        let ids: Vec<(BoneId, BoneId)> = remapped.bones.iter().map(|b| (b.id, b.parent)).collect();
+        assert_eq!(ids, [(10, ROOT_PARENT), (11, 10), (2, 11)]);
+        assert_eq!(remapped.validate(), Ok(()));
+
+        let dropped = remap_skeleton(&skeleton, &map(), false);
+        assert_eq!(dropped.bones.len(), 2);
+    }
+}
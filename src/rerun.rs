@@ -0,0 +1,132 @@
+//! Logging frames to [Rerun](https://rerun.io).
+
+use crate::{BoneId, BoneName, Frame, LogError, Skeleton};
+use ::rerun::{Points3D, Quaternion, RecordingStream, Transform3D};
+use std::time::Duration;
+
+/// The entity path of a bone, named after the bone if it is known.
+fn entity_path(id: BoneId) -> String {
+    match BoneName::try_from(id) {
+        Ok(name) => format!("skeleton/{}", name.name()),
+        Err(id) => format!("skeleton/{}", id),
+    }
+}
+
+/// Log a frame to Rerun.
+///
+/// The transforms of the frame are applied in the hierarchy of the skeleton, and the
+/// world-space transform of each bone is logged under `skeleton/<name>`. The positions of all
+/// bones are also logged as points under `skeleton/joints`. The frame is placed on the
+/// `frame` timeline by its number and on the `time` timeline by its time.
+///
+/// Bones which are not in the frame keep the transforms of the skeleton.
+pub fn log_frame(
+    rec: &RecordingStream,
+    skeleton: &Skeleton,
+    frame: &Frame,
+) -> Result<(), LogError> {
+    // skeletonの各boneをframeの姿勢にする
+    let posed = skeleton.with_frame(frame);
+    let world = posed.world_transforms()?;
+
+    rec.set_time_sequence("frame", frame.num);
+    rec.set_time("time", Duration::from_millis(frame.time as u64));
+
+    for (id, trans) in &world {
+        rec.log(
+            entity_path(*id),
+            &Transform3D::from_translation_rotation(
+                [trans.pos.x, trans.pos.y, trans.pos.z],
+                Quaternion::from_xyzw([trans.rot.x, trans.rot.y, trans.rot.z, trans.rot.w]),
+            ),
+        )?;
+    }
+    rec.log(
+        "skeleton/joints",
+        &Points3D::new(
+            world
+                .iter()
+                .map(|(_, trans)| [trans.pos.x, trans.pos.y, trans.pos.z]),
+        ),
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Bone, BoneTrans, Position, Transform, ROOT_PARENT};
+    use ::rerun::RecordingStreamBuilder;
+
+    #[test]
+    fn test_log_frame() {
+        let skeleton = Skeleton {
+            bones: vec![
+                Bone {
+                    id: 0,
+                    parent: ROOT_PARENT,
+                    trans: Transform::identity(),
+                },
+                Bone {
+                    id: 1,
+                    parent: 0,
+                    trans: Transform::identity(),
+                },
+            ],
+        };
+        let frame = Frame {
+            num: 1,
+            time: 20,
+            bones: vec![BoneTrans {
+                id: 1,
+                trans: Transform {
+                    pos: Position {
+                        x: 0.0,
+                        y: 0.5,
+                        z: 0.0,
+                    },
+                    ..Transform::identity()
+                },
+            }],
+        };
+        let (rec, storage) = RecordingStreamBuilder::new("mocopi_parser")
+            .memory()
+            .unwrap();
+
+        log_frame(&rec, &skeleton, &frame).unwrap();
+        rec.flush_blocking().unwrap();
+
+        assert!(!storage.take().is_empty());
+        assert_eq!(entity_path(1), "skeleton/torso_1");
+        assert_eq!(entity_path(100), "skeleton/100");
+    }
+
+    #[test]
+    fn test_log_frame_cycle() {
+        let skeleton = Skeleton {
+            bones: vec![
+                Bone {
+                    id: 1,
+                    parent: 2,
+                    trans: Transform::identity(),
+                },
+                Bone {
+                    id: 2,
+                    parent: 1,
+                    trans: Transform::identity(),
+                },
+            ],
+        };
+        let frame = Frame {
+            num: 1,
+            time: 20,
+            bones: Vec::new(),
+        };
+        let rec = RecordingStream::disabled();
+
+        let err = log_frame(&rec, &skeleton, &frame).unwrap_err();
+
+        assert!(matches!(err, LogError::Skeleton(_)));
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{lerp_frame, Frame};
+use alloc::vec::Vec;
+
+/// Resample frames to a fixed rate, interpolating between the input frames.
+///
+/// The input frames are placed on the timeline by their `time`. Each output frame is
+/// interpolated with [`lerp_frame`] between the input frames around its time, and the
+/// output frames are numbered from 0. The times must not wrap around, which takes 49 days.
+///
+/// # Examples
+///
+/// ```
+/// let mut resampler = mocopi_parser::Resampler::new(60.0);
+///
+/// # let frames: Vec<mocopi_parser::Frame> = Vec::new();
+/// for frame in frames {
+///     for frame in resampler.push(frame) {
+///         // 60 frames per second
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    /// The number of output frames per second.
+    fps: f64,
+    /// The time of the first output frame.
+    start: f64,
+    /// The number of output frames so far.
+    count: u32,
+    last: Option<Frame>,
+}
+
+impl Resampler {
+    /// Create a resampler which outputs `fps` frames per second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fps` is not a positive finite number.
+    pub fn new(fps: f32) -> Self {
+        assert!(
+            fps > 0.0 && fps.is_finite(),
+            "fps must be positive and finite"
+        );
+        Resampler {
+            fps: fps as f64,
+            start: 0.0,
+            count: 0,
+            last: None,
+        }
+    }
+
+    /// Add an input frame and return the output frames up to its time.
+    ///
+    /// The first frame is output as is. A frame which is not newer than the previous one,
+    /// e.g. a reordered datagram, is ignored.
+    pub fn push(&mut self, frame: Frame) -> Vec<Frame> {
+        let mut out = Vec::new();
+        let Some(last) = self.last.take() else {
+            self.start = frame.time as f64;
+            self.count = 1;
+            out.push(Frame {
+                num: 0,
+                ..frame.clone()
+            });
+            self.last = Some(frame);
+            return out;
+        };
+        if frame.time <= last.time {
+            self.last = Some(last);
+            return out;
+        }
+
+        let span = (frame.time - last.time) as f64;
+        while self.next_time() <= frame.time as f64 {
+            // 直前の出力がholdで進んでいるときは、前のframeより前にはならない
+            let t = ((self.next_time() - last.time as f64) / span).max(0.0);
+            out.push(self.output(lerp_frame(&last, &frame, t as f32)));
+        }
+        self.last = Some(frame);
+        out
+    }
+
+    /// Output the last input frame unchanged up to `time`, e.g. while the stream stalls.
+    pub fn hold_until(&mut self, time: u32) -> Vec<Frame> {
+        let mut out = Vec::new();
+        let Some(last) = self.last.clone() else {
+            return out;
+        };
+        while self.next_time() <= time as f64 {
+            out.push(self.output(last.clone()));
+        }
+        out
+    }
+
+    /// Forget the input frames, e.g. after the stream was interrupted.
+    ///
+    /// The next input frame is output as is, numbered from 0 again.
+    pub fn reset(&mut self) {
+        self.count = 0;
+        self.last = None;
+    }
+
+    fn next_time(&self) -> f64 {
+        // 誤差が積み重ならないよう、毎回最初の出力からの時間を計算する
+        self.start + self.count as f64 * 1000.0 / self.fps
+    }
+
+    /// Number the frame and place it at the next output time.
+    fn output(&mut self, frame: Frame) -> Frame {
+        let time = self.next_time();
+        let num = self.count;
+        self.count += 1;
+        Frame {
+            num,
+            // 四捨五入する
+            time: (time + 0.5) as u32,
+            ..frame
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BoneTrans, Position, Rotation, Transform};
+    use alloc::vec;
+    use core::f32::consts::FRAC_1_SQRT_2;
+
+    /// A frame whose bone moves 1 unit and turns 90° per second.
+    fn frame(num: u32, time: u32) -> Frame {
+        let t = time as f32 / 1000.0;
+        Frame {
+            num,
+            time,
+            bones: vec![BoneTrans {
+                id: 0,
+                trans: Transform {
+                    rot: Rotation::identity().slerp(
+                        &Rotation {
+                            x: 0.0,
+                            y: 0.0,
+                            z: FRAC_1_SQRT_2,
+                            w: FRAC_1_SQRT_2,
+                        },
+                        t,
+                    ),
+                    pos: Position {
+                        x: t,
+                        y: 0.0,
+                        z: 0.0,
+                    },
+                },
+            }],
+        }
+    }
+
+    #[test]
+    fn test_resample() {
+        let mut resampler = Resampler::new(60.0);
+        let mut out = Vec::new();
+
+        // 30fpsで1秒分
+        for num in 0..=30 {
+            let time = (num as f32 * 1000.0 / 30.0).round() as u32;
+            out.extend(resampler.push(frame(num, time)));
+        }
+
+        assert_eq!(out.len(), 61);
+        for (i, frame) in out.iter().enumerate() {
+            let time = i as f32 * 1000.0 / 60.0;
+            assert_eq!(frame.num, i as u32);
+            assert_eq!(frame.time, time.round() as u32);
+
+            let expected = self::frame(0, 0).bones[0]
+                .trans
+                .rot
+                .slerp(&self::frame(0, 1000).bones[0].trans.rot, time / 1000.0);
+            let trans = frame.bones[0].trans;
+            assert!((trans.pos.x - time / 1000.0).abs() < 1e-3);
+            assert!(trans.rot.approx_eq(&expected, 1e-3));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "fps must be positive and finite")]
+    fn test_resample_negative_fps() {
+        Resampler::new(-60.0);
+    }
+
+    #[test]
+    fn test_resample_hold() {
+        let mut resampler = Resampler::new(10.0);
+        assert!(resampler.hold_until(1000).is_empty());
+
+        assert_eq!(resampler.push(frame(0, 0)).len(), 1);
+        assert_eq!(resampler.push(frame(1, 150)).len(), 1);
+        // 古いframeは無視する
+        assert!(resampler.push(frame(2, 100)).is_empty());
+
+        // 届かない間は最後のframeを繰り返す
+        let held = resampler.hold_until(400);
+        assert_eq!(held.len(), 3);
+        assert_eq!(held[2].num, 4);
+        assert_eq!(held[2].time, 400);
+        assert_eq!(held[2].bones, frame(1, 150).bones);
+
+        let out = resampler.push(frame(3, 600));
+        let times: Vec<u32> = out.iter().map(|frame| frame.time).collect();
+        assert_eq!(times, [500, 600]);
+        assert_eq!(out[1].bones, frame(3, 600).bones);
+
+        resampler.reset();
+        let out = resampler.push(frame(4, 700));
+        assert_eq!(out[0].num, 0);
+        assert_eq!(out[0].time, 700);
+    }
+}
@@ -0,0 +1,532 @@
+use crate::{Bone, BoneId, Frame, Skeleton, SkeletonError, Transform};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::slice;
+
+impl Skeleton {
+    /// Find the bone with the given ID.
+    pub fn bone_by_id(&self, id: BoneId) -> Option<&Bone> {
+        self.bones.iter().find(|bone| bone.id == id)
+    }
+
+    /// Find the parent of the bone with the given ID.
+    ///
+    /// Returns `None` if the bone does not exist or if it is a root.
+    pub fn parent_of(&self, id: BoneId) -> Option<&Bone> {
+        let bone = self.bone_by_id(id)?;
+        if bone.is_root() {
+            return None;
+        }
+
+        self.bone_by_id(bone.parent)
+    }
+
+    /// The IDs of the ancestors of the bone, starting with its parent and ending with the
+    /// root.
+    ///
+    /// The walk stops at a missing parent. On a cycle, it stops before the first ID which
+    /// repeats, so every ID appears once and `id` itself is not included.
+    pub fn ancestors(&self, id: BoneId) -> Vec<BoneId> {
+        let mut ids = Vec::new();
+        let mut bone = match self.bone_by_id(id) {
+            Some(bone) => bone,
+            None => return ids,
+        };
+        let mut visited = BTreeSet::from([id]);
+        while let Some(parent) = self.parent_of(bone.id) {
+            if !visited.insert(parent.id) {
+                break;
+            }
+            ids.push(parent.id);
+            bone = parent;
+        }
+        ids
+    }
+
+    /// The number of ancestors of the bone, which is 0 for a root.
+    ///
+    /// Returns `None` if the bone does not exist or if its ancestors do not reach a root.
+    pub fn depth(&self, id: BoneId) -> Option<usize> {
+        let ancestors = self.ancestors(id);
+        let top = ancestors.last().copied().unwrap_or(id);
+        if !self.bone_by_id(top)?.is_root() {
+            return None;
+        }
+        Some(ancestors.len())
+    }
+
+    /// Build an index for looking up many bones by ID.
+    pub fn index(&self) -> BoneIndex<'_> {
+        BoneIndex {
+            bones: &self.bones,
+            positions: self
+                .bones
+                .iter()
+                .enumerate()
+                .map(|(i, bone)| (bone.id, i))
+                .collect(),
+        }
+    }
+
+    /// Check that the IDs are unique and that the parents form a tree.
+    ///
+    /// Every parent must be an existing bone or [`ROOT_PARENT`](crate::ROOT_PARENT), and
+    /// following the parents from any bone must reach a root.
+    pub fn validate(&self) -> Result<(), SkeletonError> {
+        let mut duplicates: Vec<BoneId> = Vec::new();
+        let mut ids: BTreeMap<BoneId, usize> = BTreeMap::new();
+        for (i, bone) in self.bones.iter().enumerate() {
+            if ids.insert(bone.id, i).is_some() && !duplicates.contains(&bone.id) {
+                duplicates.push(bone.id);
+            }
+        }
+        if !duplicates.is_empty() {
+            return Err(SkeletonError::DuplicateIds(duplicates));
+        }
+
+        let dangling: Vec<BoneId> = self
+            .bones
+            .iter()
+            .filter(|bone| !bone.is_root() && !ids.contains_key(&bone.parent))
+            .map(|bone| bone.id)
+            .collect();
+        if !dangling.is_empty() {
+            return Err(SkeletonError::DanglingParents(dangling));
+        }
+
+        // rootにたどり着けたboneを記録しておく
+        let mut reached = vec![false; self.bones.len()];
+        for start in 0..self.bones.len() {
+            let mut chain: Vec<usize> = Vec::new();
+            let mut i = start;
+            while !reached[i] {
+                if let Some(pos) = chain.iter().position(|&j| j == i) {
+                    let ids = chain[pos..].iter().map(|&j| self.bones[j].id).collect();
+                    return Err(SkeletonError::Cycle(ids));
+                }
+                chain.push(i);
+
+                let bone = &self.bones[i];
+                if bone.is_root() {
+                    break;
+                }
+                i = ids[&bone.parent];
+            }
+            for j in chain {
+                reached[j] = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compute the world-space transform of each bone by applying the transforms of its
+    /// ancestors.
+    ///
+    /// The transforms are returned in the order of `bones`.
+    pub fn world_transforms(&self) -> Result<Vec<(BoneId, Transform)>, SkeletonError> {
+        let index = self.index();
+        let identity = Transform::identity();
+
+        let mut world: Vec<Option<Transform>> = self.bones.iter().map(|_| None).collect();
+        for start in 0..self.bones.len() {
+            // まだ計算していない祖先をrootまでたどる
+            let mut chain: Vec<usize> = Vec::new();
+            let mut i = start;
+            let mut parent = loop {
+                if world[i].is_some() {
+                    break Some(i);
+                }
+                if let Some(pos) = chain.iter().position(|&j| j == i) {
+                    let ids = chain[pos..].iter().map(|&j| self.bones[j].id).collect();
+                    return Err(SkeletonError::Cycle(ids));
+                }
+                chain.push(i);
+
+                let bone = &self.bones[i];
+                if bone.is_root() {
+                    break None;
+                }
+                i = index
+                    .position(bone.parent)
+                    .ok_or_else(|| SkeletonError::DanglingParents(vec![bone.id]))?;
+            };
+
+            // rootに近い方から順に計算する
+            for &j in chain.iter().rev() {
+                let base = match parent {
+                    Some(p) => world[p].as_ref().unwrap_or(&identity),
+                    None => &identity,
+                };
+                world[j] = Some(base.compose(&self.bones[j].trans));
+                parent = Some(j);
+            }
+        }
+
+        Ok(self
+            .bones
+            .iter()
+            .zip(world)
+            .filter_map(|(bone, trans)| trans.map(|t| (bone.id, t)))
+            .collect())
+    }
+
+    /// The skeleton with the transforms of `frame`, matching the bones by ID.
+    ///
+    /// Bones which are not in the frame keep their transforms.
+    pub(crate) fn with_frame(&self, frame: &Frame) -> Skeleton {
+        Skeleton {
+            bones: self
+                .bones
+                .iter()
+                .map(|bone| Bone {
+                    trans: frame
+                        .bone_by_id(bone.id)
+                        .map_or(bone.trans, |other| other.trans),
+                    ..bone.clone()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Iterate over the bones in the order of `bones`.
+impl<'a> IntoIterator for &'a Skeleton {
+    type Item = &'a Bone;
+    type IntoIter = slice::Iter<'a, Bone>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.bones.iter()
+    }
+}
+
+/// Print the hierarchy as a tree, with the children of each bone indented below it.
+///
+/// Bones whose parent does not exist are printed as roots, and a bone reached again
+/// through a cycle is marked with `[cycle]`.
+impl fmt::Display for Skeleton {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let index = self.index();
+        let mut roots: Vec<usize> = Vec::new();
+        let mut children: BTreeMap<BoneId, Vec<usize>> = BTreeMap::new();
+        for (i, bone) in self.bones.iter().enumerate() {
+            if bone.is_root() || index.position(bone.parent).is_none() {
+                roots.push(i);
+            } else {
+                children.entry(bone.parent).or_default().push(i);
+            }
+        }
+
+        let mut tree = TreeWriter {
+            bones: &self.bones,
+            children,
+            printed: vec![false; self.bones.len()],
+            first: true,
+        };
+        for i in roots {
+            tree.write(f, i, "", None)?;
+        }
+        // rootからたどれなかったboneは循環の中にある
+        for i in 0..self.bones.len() {
+            if !tree.printed[i] {
+                tree.write(f, i, "", None)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct TreeWriter<'a> {
+    bones: &'a [Bone],
+    children: BTreeMap<BoneId, Vec<usize>>,
+    printed: Vec<bool>,
+    first: bool,
+}
+
+impl TreeWriter<'_> {
+    /// Write the bone at `i` and its descendants. `last` is whether the bone is the last
+    /// child of its parent, or `None` for a root.
+    fn write(
+        &mut self,
+        f: &mut fmt::Formatter<'_>,
+        i: usize,
+        prefix: &str,
+        last: Option<bool>,
+    ) -> fmt::Result {
+        let bone = &self.bones[i];
+        let (connector, indent) = match last {
+            None => ("", ""),
+            Some(false) => ("|-- ", "|   "),
+            Some(true) => ("`-- ", "    "),
+        };
+
+        if !self.first {
+            writeln!(f)?;
+        }
+        self.first = false;
+        write!(f, "{}{}{}", prefix, connector, bone.id)?;
+        if let Some(name) = bone.name() {
+            write!(f, " {}", name.name())?;
+        }
+        if self.printed[i] {
+            return write!(f, " [cycle]");
+        }
+        self.printed[i] = true;
+
+        let children = self.children.get(&bone.id).cloned().unwrap_or_default();
+        let prefix = format!("{}{}", prefix, indent);
+        for (n, &child) in children.iter().enumerate() {
+            self.write(f, child, &prefix, Some(n + 1 == children.len()))?;
+        }
+        Ok(())
+    }
+}
+
+/// An index of the bones in a [`Skeleton`] by their IDs.
+#[derive(Debug)]
+pub struct BoneIndex<'a> {
+    bones: &'a [Bone],
+    positions: BTreeMap<BoneId, usize>,
+}
+
+impl<'a> BoneIndex<'a> {
+    /// Find the bone with the given ID.
+    pub fn get(&self, id: BoneId) -> Option<&'a Bone> {
+        self.position(id).map(|i| &self.bones[i])
+    }
+
+    /// The position of the bone in `Skeleton.bones`.
+    pub fn position(&self, id: BoneId) -> Option<usize> {
+        self.positions.get(&id).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Position, Rotation, Transform};
+    use alloc::string::ToString;
+
+    fn bone(id: BoneId, parent: BoneId) -> Bone {
+        Bone {
+            id,
+            parent,
+            trans: Transform {
+                rot: Rotation::identity(),
+                pos: Position {
+                    x: 0.0,
+                    y: id as f32,
+                    z: 0.0,
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn test_bone_by_id() {
+        let skeleton = Skeleton {
+            bones: vec![bone(0, 0xffff), bone(2, 1), bone(1, 0)],
+        };
+
+        assert_eq!(skeleton.bone_by_id(2).unwrap().id, 2);
+        assert!(skeleton.bone_by_id(3).is_none());
+
+        assert_eq!(skeleton.parent_of(2).unwrap().id, 1);
+        assert_eq!(skeleton.parent_of(1).unwrap().id, 0);
+        assert!(skeleton.parent_of(0).is_none());
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let skeleton = Skeleton {
+            bones: vec![bone(0, 0xffff), bone(2, 1), bone(1, 0)],
+        };
+
+        let mut ids = Vec::new();
+        for bone in &skeleton {
+            ids.push(bone.id);
+        }
+        assert_eq!(ids, [0, 2, 1]);
+    }
+
+    #[test]
+    fn test_index() {
+        let skeleton = Skeleton {
+            bones: vec![bone(0, 0xffff), bone(2, 1), bone(1, 0)],
+        };
+        let index = skeleton.index();
+
+        assert_eq!(index.position(1), Some(2));
+        assert_eq!(index.get(2).unwrap().parent, 1);
+        assert!(index.get(3).is_none());
+    }
+
+    #[test]
+    fn test_world_transforms() {
+        use core::f32::consts::FRAC_1_SQRT_2;
+
+        // rootはZ軸まわりに90°回転している
+        let mut root = bone(0, crate::ROOT_PARENT);
+        root.trans.rot = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: FRAC_1_SQRT_2,
+            w: FRAC_1_SQRT_2,
+        };
+        root.trans.pos = Position {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let mut child = bone(1, 0);
+        child.trans.pos = Position {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let skeleton = Skeleton {
+            bones: vec![child, root],
+        };
+
+        let world = skeleton.world_transforms().unwrap();
+
+        assert_eq!(world[0].0, 1);
+        assert_eq!(world[1].0, 0);
+        assert_eq!(world[1].1.pos.x, 1.0);
+
+        let pos = &world[0].1.pos;
+        assert!((pos.x - 1.0).abs() < 1e-6);
+        assert!((pos.y - 1.0).abs() < 1e-6);
+        assert!(pos.z.abs() < 1e-6);
+        assert!((world[0].1.rot.z - FRAC_1_SQRT_2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_world_transforms_cycle() {
+        let skeleton = Skeleton {
+            bones: vec![bone(0, crate::ROOT_PARENT), bone(1, 2), bone(2, 1)],
+        };
+
+        let err = skeleton.world_transforms().unwrap_err();
+
+        assert_eq!(err, SkeletonError::Cycle(vec![1, 2]));
+    }
+
+    #[test]
+    fn test_validate() {
+        let skeleton = Skeleton {
+            bones: vec![bone(0, crate::ROOT_PARENT), bone(2, 1), bone(1, 0)],
+        };
+        assert_eq!(skeleton.validate(), Ok(()));
+
+        let skeleton = Skeleton {
+            bones: vec![bone(0, crate::ROOT_PARENT), bone(1, 0), bone(1, 0)],
+        };
+        assert_eq!(
+            skeleton.validate(),
+            Err(SkeletonError::DuplicateIds(vec![1]))
+        );
+
+        let skeleton = Skeleton {
+            bones: vec![bone(0, crate::ROOT_PARENT), bone(1, 5), bone(2, 6)],
+        };
+        assert_eq!(
+            skeleton.validate(),
+            Err(SkeletonError::DanglingParents(vec![1, 2]))
+        );
+
+        let skeleton = Skeleton {
+            bones: vec![
+                bone(0, crate::ROOT_PARENT),
+                bone(1, 3),
+                bone(2, 1),
+                bone(3, 2),
+            ],
+        };
+        assert_eq!(
+            skeleton.validate(),
+            Err(SkeletonError::Cycle(vec![1, 3, 2]))
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let skeleton = Skeleton {
+            bones: vec![
+                bone(0, crate::ROOT_PARENT),
+                bone(1, 0),
+                bone(2, 1),
+                bone(11, 0),
+                bone(100, crate::ROOT_PARENT),
+            ],
+        };
+
+        assert_eq!(
+            skeleton.to_string(),
+            "0 root\n\
+             |-- 1 torso_1\n\
+             |   `-- 2 torso_2\n\
+             `-- 11 l_shoulder\n\
+             100"
+        );
+    }
+
+    #[test]
+    fn test_display_cycle() {
+        let skeleton = Skeleton {
+            bones: vec![bone(0, crate::ROOT_PARENT), bone(1, 2), bone(2, 1)],
+        };
+
+        assert_eq!(
+            skeleton.to_string(),
+            "0 root\n\
+             1 torso_1\n\
+             `-- 2 torso_2\n    \
+             `-- 1 torso_1 [cycle]"
+        );
+    }
+
+    #[test]
+    fn test_ancestors() {
+        let skeleton = Skeleton {
+            bones: vec![
+                bone(0, crate::ROOT_PARENT),
+                bone(2, 1),
+                bone(1, 0),
+                bone(3, 2),
+            ],
+        };
+
+        assert_eq!(skeleton.ancestors(3), [2, 1, 0]);
+        assert!(skeleton.ancestors(0).is_empty());
+        assert!(skeleton.ancestors(4).is_empty());
+
+        assert_eq!(skeleton.depth(3), Some(3));
+        assert_eq!(skeleton.depth(1), Some(1));
+        assert_eq!(skeleton.depth(0), Some(0));
+        assert_eq!(skeleton.depth(4), None);
+    }
+
+    #[test]
+    fn test_ancestors_cycle() {
+        let skeleton = Skeleton {
+            bones: vec![
+                bone(0, crate::ROOT_PARENT),
+                bone(1, 2),
+                bone(2, 1),
+                bone(3, 5),
+                bone(4, 1),
+            ],
+        };
+
+        // 2つ目の1の前で止まる
+        assert_eq!(skeleton.ancestors(1), [2]);
+        assert_eq!(skeleton.ancestors(4), [1, 2]);
+        assert_eq!(skeleton.depth(1), None);
+        assert!(skeleton.ancestors(3).is_empty());
+        assert_eq!(skeleton.depth(3), None);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::frame::lerp_trans;
+use crate::{BoneId, BoneTrans, Frame, Transform};
+use alloc::collections::BTreeMap;
+
+/// Smooth the jitter of frames with an exponential moving average for each bone.
+///
+/// Each output moves from the previous output toward the input by `alpha`: positions
+/// linearly and rotations with [`Rotation::slerp`](crate::Rotation::slerp). A bone seen for
+/// the first time passes through unchanged.
+#[derive(Debug, Clone)]
+pub struct Smoother {
+    alpha: f32,
+    state: BTreeMap<BoneId, Transform>,
+}
+
+impl Smoother {
+    /// Create a smoother with `alpha` between 0 and 1.
+    ///
+    /// `1` passes the frames through unchanged, and smaller values smooth them more. Values
+    /// outside the range are clamped to it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` is NaN.
+    pub fn new(alpha: f32) -> Self {
+        assert!(!alpha.is_nan(), "alpha must not be NaN");
+        Smoother {
+            alpha: alpha.clamp(0.0, 1.0),
+            state: BTreeMap::new(),
+        }
+    }
+
+    /// Smooth the frame and remember the result for the next one.
+    pub fn apply(&mut self, frame: &Frame) -> Frame {
+        let bones = frame
+            .bones
+            .iter()
+            .map(|bone| {
+                let trans = match self.state.get(&bone.id) {
+                    Some(prev) => lerp_trans(prev, &bone.trans, self.alpha),
+                    None => bone.trans,
+                };
+                self.state.insert(bone.id, trans);
+                BoneTrans { id: bone.id, trans }
+            })
+            .collect();
+
+        Frame {
+            num: frame.num,
+            time: frame.time,
+            bones,
+        }
+    }
+
+    /// Forget the previous frames, e.g. after the stream was interrupted.
+    pub fn reset(&mut self) {
+        self.state.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Position, Rotation};
+    use alloc::vec;
+    use core::f32::consts::FRAC_1_SQRT_2;
+
+    fn frame(x: f32, rot: Rotation) -> Frame {
+        Frame {
+            num: 0,
+            time: 0,
+            bones: vec![BoneTrans {
+                id: 0,
+                trans: Transform {
+                    rot,
+                    pos: Position { x, y: 0.0, z: 0.0 },
+                },
+            }],
+        }
+    }
+
+    #[test]
+    fn test_smoother_step() {
+        let z90 = Rotation {
+            x: 0.0,
+            y: 0.0,
+            z: FRAC_1_SQRT_2,
+            w: FRAC_1_SQRT_2,
+        };
+        let mut smoother = Smoother::new(0.5);
+
+        // 初めてのboneはそのまま
+        let first = smoother.apply(&frame(0.0, Rotation::identity()));
+        assert_eq!(first, frame(0.0, Rotation::identity()));
+
+        let mut prev = 0.0;
+        for _ in 0..10 {
+            let out = smoother.apply(&frame(1.0, z90));
+            let x = out.bones[0].trans.pos.x;
+            assert!(x > prev && x < 1.0);
+            prev = x;
+        }
+        let last = smoother.apply(&frame(1.0, z90)).bones[0].trans;
+        assert!(last.approx_eq(&frame(1.0, z90).bones[0].trans, 1e-3));
+
+        smoother.reset();
+        assert_eq!(smoother.apply(&frame(0.0, z90)), frame(0.0, z90));
+    }
+
+    #[test]
+    #[should_panic(expected = "alpha must not be NaN")]
+    fn test_smoother_nan() {
+        Smoother::new(f32::NAN);
+    }
+}
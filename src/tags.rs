@@ -0,0 +1,55 @@
+//! The names of the sections in the packets, which the parser recognizes.
+
+/// The head of a packet, which has [`FTYP`] and [`VRSN`].
+pub const HEAD: &str = "head";
+/// The format of the packet.
+pub const FTYP: &str = "ftyp";
+/// The version of the packet layout.
+pub const VRSN: &str = "vrsn";
+/// The info about the sender, which has [`IPAD`] and [`RCVP`].
+pub const SNDF: &str = "sndf";
+/// The address of the sender.
+pub const IPAD: &str = "ipad";
+/// The port of the sender.
+pub const RCVP: &str = "rcvp";
+/// The body of a skeleton packet, which has [`BONS`].
+pub const SKDF: &str = "skdf";
+/// The bones of a skeleton, each of which is a [`BNDT`].
+pub const BONS: &str = "bons";
+/// A bone of a skeleton, which has [`BNID`], [`PBID`] and [`TRAN`].
+pub const BNDT: &str = "bndt";
+/// The ID of a bone.
+pub const BNID: &str = "bnid";
+/// The ID of the parent of a bone.
+pub const PBID: &str = "pbid";
+/// The body of a frame packet, which has [`FNUM`], [`TIME`] and [`BTRS`].
+pub const FRAM: &str = "fram";
+/// The number of a frame.
+pub const FNUM: &str = "fnum";
+/// The time of a frame.
+pub const TIME: &str = "time";
+/// The bones of a frame, each of which is a [`BTDT`].
+pub const BTRS: &str = "btrs";
+/// A bone of a frame, which has [`BNID`] and [`TRAN`].
+pub const BTDT: &str = "btdt";
+/// The transform of a bone.
+pub const TRAN: &str = "tran";
+
+/// All the names above.
+pub const ALL: [&str; 17] = [
+    HEAD, FTYP, VRSN, SNDF, IPAD, RCVP, SKDF, BONS, BNDT, BNID, PBID, FRAM, FNUM, TIME, BTRS, BTDT,
+    TRAN,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tags() {
+        for tag in ALL {
+            assert_eq!(tag.len(), 4, "{}", tag);
+            assert!(tag.bytes().all(|b| b.is_ascii_lowercase()));
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! Packets shared by the tests of the modules.
+
+use crate::{BoneTrans, Frame, FramePacket, Head, Info, Transform, EXPECTED_FORMAT};
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::net::Ipv4Addr;
+
+/// The head which mocopi sends.
+pub(crate) fn head() -> Head {
+    Head {
+        format: EXPECTED_FORMAT.to_string(),
+        ver: 1,
+        ver_minor: None,
+    }
+}
+
+/// The info of a sender without an address.
+pub(crate) fn info() -> Info {
+    Info {
+        addr: Ipv4Addr::UNSPECIFIED.into(),
+        port: 12351,
+    }
+}
+
+/// A frame packet with one bone at the origin. The frames are 20 ms apart.
+pub(crate) fn frame_packet(num: u32) -> FramePacket {
+    FramePacket {
+        head: head(),
+        info: info(),
+        frame: Frame {
+            num,
+            time: num * 20,
+            bones: vec![BoneTrans {
+                id: 0,
+                trans: Transform::identity(),
+            }],
+        },
+        extra: Vec::new(),
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{Head, ParseError};
+use core::fmt;
+
+/// The versions of the packet layout, read from [`Head::ver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    V1,
+}
+
+impl Version {
+    /// The newest version which this crate can parse.
+    pub const LATEST: Version = Version::V1;
+
+    /// The value of `vrsn` for this version.
+    pub fn number(&self) -> u8 {
+        match self {
+            Version::V1 => 1,
+        }
+    }
+}
+
+impl TryFrom<u8> for Version {
+    type Error = ParseError;
+
+    fn try_from(ver: u8) -> Result<Self, Self::Error> {
+        match ver {
+            1 => Ok(Version::V1),
+            ver => Err(ParseError::UnsupportedVersion(ver)),
+        }
+    }
+}
+
+/// The version number in `vrsn`, read from [`Head::version_number`].
+///
+/// A 1-byte `vrsn` is the major version with the minor version 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VersionNumber {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl fmt::Display for VersionNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl Head {
+    /// The version of the packet layout.
+    pub fn version(&self) -> Result<Version, ParseError> {
+        Version::try_from(self.ver)
+    }
+
+    /// The version number in `vrsn`.
+    pub fn version_number(&self) -> VersionNumber {
+        VersionNumber {
+            major: self.ver,
+            minor: self.ver_minor.unwrap_or(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_version() {
+        assert_eq!(Version::try_from(1), Ok(Version::V1));
+        assert_eq!(Version::V1.number(), 1);
+        assert_eq!(Version::try_from(2), Err(ParseError::UnsupportedVersion(2)));
+    }
+
+    #[test]
+    fn test_version_number() {
+        let version = VersionNumber { major: 1, minor: 2 };
+        assert_eq!(version.to_string(), "1.2");
+        assert!(version > VersionNumber { major: 1, minor: 0 });
+        assert!(version < VersionNumber { major: 2, minor: 0 });
+    }
+}
@@ -0,0 +1,40 @@
+//! A WebAssembly entry point, enabled by the `wasm` feature.
+
+use crate::{parse, SkeletonOrFrame};
+use alloc::string::{String, ToString};
+use wasm_bindgen::prelude::*;
+
+/// Parse a packet and serialize it into JSON.
+///
+/// The JSON is a skeleton packet or a frame packet, which have a `skdf` or a `fram` field
+/// respectively, in the shape described in [`json`](crate::json). A failure is thrown as a string describing the error.
+#[wasm_bindgen]
+pub fn parse_to_json(bytes: &[u8]) -> Result<String, JsValue> {
+    to_json(bytes).map_err(|e| JsValue::from_str(&e))
+}
+
+fn to_json(bytes: &[u8]) -> Result<String, String> {
+    let json = match parse(bytes).map_err(|e| e.to_string())? {
+        SkeletonOrFrame::Skeleton(packet) => serde_json::to_string(&packet),
+        SkeletonOrFrame::Frame(packet) => serde_json::to_string(&packet),
+    };
+    json.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode_frame;
+    use crate::json::frame_from_json;
+    use crate::test_util::frame_packet;
+
+    #[test]
+    fn test_to_json() {
+        let packet = frame_packet(1);
+
+        let json = to_json(&encode_frame(&packet).unwrap()).unwrap();
+        assert_eq!(frame_from_json(&json).unwrap(), packet);
+
+        assert!(to_json(&[0x00; 4]).is_err());
+    }
+}